@@ -7,6 +7,8 @@ use proc_macro_error::{proc_macro_error, abort};
 use syn::spanned::Spanned;
 use venial::{parse_item, Fields, Item};
 
+mod lua_type;
+
 #[proc_macro_error]
 #[proc_macro_derive(UserData)]
 pub fn derive_user_data(input: TokenStream) -> TokenStream {
@@ -25,12 +27,12 @@ pub fn derive_user_data(input: TokenStream) -> TokenStream {
     quote!(
         impl mlua_extras::mlua::UserData for #name {
             fn add_fields<F: mlua_extras::mlua::UserDataFields<Self>>(fields: &mut F) {
-                let mut wrapper = mlua_extras::typed::WrappedGenerator::new(fields);
+                let mut wrapper = mlua_extras::typed::WrappedBuilder::new(fields);
                 <#name as mlua_extras::typed::TypedUserData>::add_fields(&mut wrapper);
             }
 
             fn add_methods<M: mlua_extras::mlua::UserDataMethods<Self>>(methods: &mut M) {
-                let mut wrapper = mlua_extras::typed::WrappedGenerator::new(methods);
+                let mut wrapper = mlua_extras::typed::WrappedBuilder::new(methods);
                 <#name as mlua_extras::typed::TypedUserData>::add_methods(&mut wrapper);
             }
         }
@@ -45,13 +47,40 @@ pub fn derive_typed(input: TokenStream) -> TokenStream {
         Ok(Item::Struct(struct_type)) => {
             let name = struct_type.name.clone();
             let value = syn::LitStr::new(name.to_string().as_str(), Span::call_site());
-            quote!(
-                impl mlua_extras::typed::Typed for #name {
-                    fn ty() -> mlua_extras::typed::Type {
-                        mlua_extras::typed::Type::single(#value)
+
+            // `#[typed(record)]` uses the struct's fields as the type, i.e. `{ name: string }`
+            let record = struct_type.attributes.iter().any(|attr| {
+                attr.get_single_path_segment().map(|v| v == "typed").unwrap_or_default()
+                    && attr.get_value_tokens().iter().any(|v| v.to_string() == "record")
+            });
+
+            match (&struct_type.fields, record) {
+                (Fields::Named(named), true) => {
+                    let fields = named.fields.iter().map(|(field, _)| {
+                        let name = field.name.to_string();
+                        let ty = field.ty.clone();
+                        quote!{ (std::borrow::Cow::Borrowed(#name), <#ty as mlua_extras::typed::Typed>::ty()) }
+                    }).collect::<Vec<_>>();
+
+                    quote!(
+                        impl mlua_extras::typed::Typed for #name {
+                            fn ty() -> mlua_extras::typed::Type {
+                                mlua_extras::typed::Type::Struct(std::collections::BTreeMap::from([
+                                    #(#fields,)*
+                                ]))
+                            }
+                        }
+                    )
+                },
+                (_, true) => abort!(struct_type.name.span(), "`#[typed(record)]` is only supported for structs with named fields"),
+                _ => quote!(
+                    impl mlua_extras::typed::Typed for #name {
+                        fn ty() -> mlua_extras::typed::Type {
+                            mlua_extras::typed::Type::reference(#value)
+                        }
                     }
-                }
-            )
+                ),
+            }
         },
         Ok(Item::Enum(enum_type)) => {
             let variants = enum_type.variants
@@ -79,9 +108,9 @@ pub fn derive_typed(input: TokenStream) -> TokenStream {
                             let tuple_values = named.fields.iter().map(|(field, _)| {
                                 let name = field.name.to_string();
                                 let ty = field.ty.clone();
-                                quote!{ (#name, <#ty as mlua_extras::typed::Typed>::ty()) }
+                                quote!{ (std::borrow::Cow::Borrowed(#name), <#ty as mlua_extras::typed::Typed>::ty()) }
                             }).collect::<Vec<_>>();
-                            quote!{ mlua_extras::typed::Type::Struct(std::collections::HashMap::from([
+                            quote!{ mlua_extras::typed::Type::Struct(std::collections::BTreeMap::from([
                                     #(#tuple_values,)*
                             ])) }
                         }
@@ -108,3 +137,27 @@ pub fn derive_typed(input: TokenStream) -> TokenStream {
         _ => abort!(input.span(), "only `struct` and `enum` types are supported for Typed")
     }.into()
 }
+
+/// Build a `mlua_extras::typed::Type` from a LuaLS type expression that is checked at compile time
+///
+/// # Example
+///
+/// ```ignore
+/// use mlua_extras::lua_type;
+///
+/// let ty = lua_type!("table<string, fun(x: integer): boolean>");
+/// let record = lua_type!("{ name: string, tags?: string[] }");
+/// ```
+#[proc_macro_error]
+#[proc_macro]
+pub fn lua_type(input: TokenStream) -> TokenStream {
+    let source = match syn::parse::<syn::LitStr>(input) {
+        Ok(source) => source,
+        Err(err) => abort!(err.span(), "expected a string literal, i.e. `lua_type!(\"string|nil\")`"),
+    };
+
+    match mlua_extras_grammar::parse(&source.value()) {
+        Ok(ty) => lua_type::expand(&ty).into(),
+        Err(err) => abort!(source.span(), "invalid lua type: {}", err),
+    }
+}
@@ -0,0 +1,72 @@
+//! Compile time expansion of LuaLS type expressions for the `lua_type!` macro
+//!
+//! The expression is parsed with `mlua_extras_grammar`, the same parser as
+//! `mlua_extras::typed::Type`'s `FromStr`, and expands to the code that builds the equivalent
+//! `Type`, so there is nothing left to parse at runtime.
+
+use mlua_extras_grammar::Ty;
+use proc_macro2::TokenStream as TokenStream2;
+
+/// Expand a parsed type into the code that builds its `mlua_extras::typed::Type`
+pub fn expand(ty: &Ty) -> TokenStream2 {
+    let ty_path = quote!(mlua_extras::typed::Type);
+    match ty {
+        Ty::Single(value) => quote!(#ty_path::single(#value)),
+        Ty::Ref(name) => quote!(#ty_path::reference(#name)),
+        Ty::LiteralString(value) => quote!(#ty_path::literal_string(#value)),
+        Ty::Optional(inner) => {
+            let inner = expand(inner);
+            quote!((#inner | #ty_path::single("nil")))
+        }
+        Ty::Union(types) => {
+            let types = types.iter().map(expand);
+            quote!(#ty_path::Union(::std::vec![ #(#types,)* ]))
+        }
+        Ty::Array(inner) => {
+            let inner = expand(inner);
+            quote!(#ty_path::Array(::std::boxed::Box::new(#inner)))
+        }
+        Ty::Map(key, value) => {
+            let key = expand(key);
+            let value = expand(value);
+            quote!(#ty_path::Map(
+                ::std::boxed::Box::new(#key),
+                ::std::boxed::Box::new(#value)
+            ))
+        }
+        Ty::Variadic(inner) => {
+            let inner = expand(inner);
+            quote!(#ty_path::Variadic(::std::boxed::Box::new(#inner)))
+        }
+        Ty::Tuple(types) => {
+            let types = types.iter().map(expand);
+            quote!(#ty_path::Tuple(::std::vec![ #(#types,)* ]))
+        }
+        Ty::Struct(fields) => {
+            let names = fields.iter().map(|(name, _)| name);
+            let types = fields.iter().map(|(_, ty)| expand(ty));
+            quote!(#ty_path::Struct(::std::collections::BTreeMap::from([
+                #((::std::borrow::Cow::Borrowed(#names), #types),)*
+            ])))
+        }
+        Ty::Function {
+            params,
+            returns,
+            is_async,
+        } => {
+            let params = params.iter().map(|(name, ty)| {
+                let ty = expand(ty);
+                match name {
+                    Some(name) => quote!(mlua_extras::typed::Param::from((#name, #ty))),
+                    None => quote!(mlua_extras::typed::Param::from(#ty)),
+                }
+            });
+            let returns = returns.iter().map(expand);
+            quote!(#ty_path::Function {
+                params: ::std::vec![ #(#params,)* ],
+                returns: ::std::vec![ #(::std::convert::From::from(#returns),)* ],
+                is_async: #is_async,
+            })
+        }
+    }
+}
@@ -0,0 +1,596 @@
+//! Parser for [LuaLS](https://luals.github.io/wiki/annotations/) type expressions
+//!
+//! Both `mlua_extras::typed::Type`'s `FromStr` and the `lua_type!` macro parse with this crate so
+//! the runtime and compile time grammars can't drift apart. The parsed [`Ty`] mirrors the
+//! `Type` that each of them builds from it.
+
+pub type ParseResult<T> = Result<T, String>;
+
+/// Types that are built into LuaLS. Any other name is a reference to a class, alias, or enum
+#[rustfmt::skip]
+pub const BUILTIN_TYPES: &[&str] = &[
+    "any", "boolean", "false", "function", "integer", "lightuserdata", "nil", "number", "self",
+    "string", "table", "thread", "true", "unknown", "userdata",
+];
+
+/// Parsed type expression mirroring `mlua_extras::typed::Type`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Ty {
+    /// Builtin type, number literal, or an expression that is kept as it was written
+    Single(String),
+    /// Name of a class, alias, or enum
+    Ref(String),
+    /// String literal, the value is without quotes
+    LiteralString(String),
+    /// Union of the type and `nil`. i.e. `string?`
+    Optional(Box<Ty>),
+    Union(Vec<Ty>),
+    Array(Box<Ty>),
+    Map(Box<Ty>, Box<Ty>),
+    Variadic(Box<Ty>),
+    Tuple(Vec<Ty>),
+    /// Named table fields in the order they were written
+    Struct(Vec<(String, Ty)>),
+    Function {
+        params: Vec<(Option<String>, Ty)>,
+        returns: Vec<Ty>,
+        is_async: bool,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Token<'a> {
+    Ident(&'a str),
+    Str(&'a str),
+    Number(&'a str),
+    Ellipsis,
+    Punct(char),
+}
+
+impl std::fmt::Display for Token<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Ident(v) | Self::Number(v) => write!(f, "{v}"),
+            Self::Str(v) => write!(f, "\"{v}\""),
+            Self::Ellipsis => write!(f, "..."),
+            Self::Punct(v) => write!(f, "{v}"),
+        }
+    }
+}
+
+/// Splits a LuaLS type expression into tokens on demand
+#[derive(Debug, Clone)]
+struct Lexer<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl<'a> Lexer<'a> {
+    /// Get the next token along with it's end offset without consuming it
+    fn peek_span(&self) -> ParseResult<Option<(Token<'a>, usize)>> {
+        let start = self.start();
+        let rest = &self.src[start..];
+
+        let Some(first) = rest.chars().next() else {
+            return Ok(None);
+        };
+
+        let take_while = |pred: fn(char) -> bool| {
+            rest.char_indices()
+                .find(|(_, c)| !pred(*c))
+                .map(|(i, _)| i)
+                .unwrap_or(rest.len())
+        };
+
+        Ok(Some(match first {
+            c if c.is_alphabetic() || c == '_' => {
+                let len = take_while(|c| c.is_alphanumeric() || c == '_' || c == '.');
+                (Token::Ident(&rest[..len]), start + len)
+            }
+            c if c.is_ascii_digit()
+                || (c == '-' && rest[1..].starts_with(|c: char| c.is_ascii_digit())) =>
+            {
+                let len = 1 + rest[1..]
+                    .char_indices()
+                    .find(|(_, c)| !(c.is_alphanumeric() || *c == '.'))
+                    .map(|(i, _)| i)
+                    .unwrap_or(rest.len() - 1);
+                (Token::Number(&rest[..len]), start + len)
+            }
+            quote @ ('"' | '\'' | '`') => {
+                let mut escaped = false;
+                let end = rest[1..]
+                    .char_indices()
+                    .find(|(_, c)| {
+                        let found = !escaped && *c == quote;
+                        escaped = !escaped && *c == '\\';
+                        found
+                    })
+                    .map(|(i, _)| i + 1)
+                    .ok_or_else(|| format!("unterminated string literal `{rest}`"))?;
+                (Token::Str(&rest[1..end]), start + end + 1)
+            }
+            '.' if rest.starts_with("...") => (Token::Ellipsis, start + 3),
+            c => (Token::Punct(c), start + c.len_utf8()),
+        }))
+    }
+
+    fn peek(&self) -> ParseResult<Option<Token<'a>>> {
+        Ok(self.peek_span()?.map(|(token, _)| token))
+    }
+
+    fn next(&mut self) -> ParseResult<Option<Token<'a>>> {
+        Ok(self.peek_span()?.map(|(token, end)| {
+            self.pos = end;
+            token
+        }))
+    }
+
+    /// Offset of the start of the next token
+    fn start(&self) -> usize {
+        let rest = &self.src[self.pos..];
+        self.pos + (rest.len() - rest.trim_start().len())
+    }
+}
+
+/// Parse a complete LuaLS type expression, i.e. `fun(name: string): integer|nil`
+pub fn parse(src: &str) -> ParseResult<Ty> {
+    let mut parser = Parser::new(src);
+    let ty = parser.parse_type()?;
+    match parser.lexer.peek()? {
+        Some(token) => Err(format!("unexpected `{token}` in type `{src}`")),
+        None => Ok(ty),
+    }
+}
+
+/// Parse a type expression from the start of the source returning the remaining text
+pub fn parse_prefix(src: &str) -> ParseResult<(Ty, &str)> {
+    let mut parser = Parser::new(src);
+    let ty = parser.parse_type()?;
+    Ok((ty, &src[parser.lexer.pos..]))
+}
+
+/// Recursive descent parser for LuaLS type expressions
+struct Parser<'a> {
+    lexer: Lexer<'a>,
+    /// How many brackets deep the parser is. Multiple function returns are only allowed at the top
+    /// level since commas are otherwise ambiguous.
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(src: &'a str) -> Self {
+        Self {
+            lexer: Lexer { src, pos: 0 },
+            depth: 0,
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> ParseResult<()> {
+        match self.lexer.next()? {
+            Some(Token::Punct(c)) if c == expected => Ok(()),
+            Some(token) => Err(format!(
+                "expected `{expected}` but found `{token}` in type `{}`",
+                self.lexer.src
+            )),
+            None => Err(format!(
+                "expected `{expected}` but reached the end of type `{}`",
+                self.lexer.src
+            )),
+        }
+    }
+
+    fn is_next(&self, expected: char) -> ParseResult<bool> {
+        Ok(self.lexer.peek()? == Some(Token::Punct(expected)))
+    }
+
+    fn parse_type(&mut self) -> ParseResult<Ty> {
+        let mut types = Vec::from([self.parse_postfix()?]);
+        while self.is_next('|')? {
+            self.lexer.next()?;
+            types.push(self.parse_postfix()?);
+        }
+
+        Ok(match types.len() {
+            1 => types.remove(0),
+            _ => Ty::Union(types),
+        })
+    }
+
+    fn parse_postfix(&mut self) -> ParseResult<Ty> {
+        let mut ty = self.parse_primary()?;
+        loop {
+            if self.is_next('?')? {
+                self.lexer.next()?;
+                ty = Ty::Optional(Box::new(ty));
+                continue;
+            }
+
+            let mut lookahead = self.lexer.clone();
+            if lookahead.next()? == Some(Token::Punct('['))
+                && lookahead.next()? == Some(Token::Punct(']'))
+            {
+                self.lexer = lookahead;
+                ty = Ty::Array(Box::new(ty));
+                continue;
+            }
+            return Ok(ty);
+        }
+    }
+
+    fn parse_primary(&mut self) -> ParseResult<Ty> {
+        let start = self.lexer.start();
+        let src = self.lexer.src;
+        match self.lexer.next()? {
+            Some(Token::Ellipsis) => match self.lexer.peek()? {
+                Some(
+                    Token::Ident(_) | Token::Str(_) | Token::Number(_) | Token::Punct('(' | '{'),
+                ) => Ok(Ty::Variadic(Box::new(self.parse_postfix()?))),
+                _ => Ok(Ty::Variadic(Box::new(Ty::Single("any".into())))),
+            },
+            Some(Token::Str(value)) => Ok(Ty::LiteralString(value.into())),
+            Some(Token::Number(value)) => Ok(Ty::Single(value.into())),
+            Some(Token::Punct('(')) => {
+                self.depth += 1;
+                let ty = self.parse_type()?;
+                self.expect(')')?;
+                self.depth -= 1;
+                Ok(ty)
+            }
+            Some(Token::Punct('{')) => self.parse_table(start),
+            Some(Token::Ident("fun")) => self.parse_function(false),
+            Some(Token::Ident("async")) if self.lexer.peek()? == Some(Token::Ident("fun")) => {
+                self.lexer.next()?;
+                self.parse_function(true)
+            }
+            Some(Token::Ident(name)) => {
+                if !self.is_next('<')? {
+                    return Ok(if BUILTIN_TYPES.contains(&name) {
+                        Ty::Single(name.into())
+                    } else {
+                        Ty::Ref(name.into())
+                    });
+                }
+
+                self.lexer.next()?;
+                self.depth += 1;
+                let mut args = Vec::from([self.parse_type()?]);
+                while self.is_next(',')? {
+                    self.lexer.next()?;
+                    args.push(self.parse_type()?);
+                }
+                self.expect('>')?;
+                self.depth -= 1;
+
+                if name == "table" && args.len() == 2 {
+                    let value = args.pop().unwrap();
+                    let key = args.pop().unwrap();
+                    Ok(Ty::Map(Box::new(key), Box::new(value)))
+                } else {
+                    Ok(Ty::Single(src[start..self.lexer.pos].into()))
+                }
+            }
+            Some(token) => Err(format!(
+                "expected a type but found `{token}` in type `{src}`"
+            )),
+            None => Err(format!(
+                "expected a type but reached the end of type `{src}`"
+            )),
+        }
+    }
+
+    /// Table literals: `{ [K]: V }` is a map, `{ [1]: A, [2]: B }` is a tuple, and
+    /// `{ name: A, other?: B }` is a struct. Other table literals are kept as they were written.
+    fn parse_table(&mut self, start: usize) -> ParseResult<Ty> {
+        self.depth += 1;
+        let mut keys = Vec::new();
+        let mut fields: Vec<(String, Ty)> = Vec::new();
+        let mut values = Vec::new();
+
+        while !self.is_next('}')? {
+            let mut name = None;
+            if self.is_next('[')? {
+                self.lexer.next()?;
+                keys.push(self.parse_type()?);
+                self.expect(']')?;
+            } else {
+                match self.lexer.next()? {
+                    Some(Token::Ident(key) | Token::Str(key)) => name = Some(key),
+                    Some(token) => {
+                        return Err(format!(
+                            "expected a table key but found `{token}` in type `{}`",
+                            self.lexer.src
+                        ))
+                    }
+                    None => {
+                        return Err(format!(
+                            "expected `}}` but reached the end of type `{}`",
+                            self.lexer.src
+                        ))
+                    }
+                }
+            }
+
+            let optional = name.is_some() && self.is_next('?')?;
+            if optional {
+                self.lexer.next()?;
+            }
+            self.expect(':')?;
+            let mut ty = self.parse_type()?;
+
+            match name {
+                Some(name) => {
+                    if optional {
+                        ty = Ty::Optional(Box::new(ty));
+                    }
+                    if fields.iter().any(|(field, _)| field == name) {
+                        return Err(format!(
+                            "duplicate table key `{name}` in type `{}`",
+                            self.lexer.src
+                        ));
+                    }
+                    fields.push((name.to_string(), ty));
+                }
+                None => values.push(ty),
+            }
+
+            if self.is_next(',')? || self.is_next(';')? {
+                self.lexer.next()?;
+            }
+        }
+        self.expect('}')?;
+        self.depth -= 1;
+
+        if keys.is_empty() && !fields.is_empty() {
+            return Ok(Ty::Struct(fields));
+        }
+
+        let is_tuple = fields.is_empty()
+            && !keys.is_empty()
+            && keys
+                .iter()
+                .enumerate()
+                .all(|(i, key)| *key == Ty::Single((i + 1).to_string()));
+
+        Ok(if is_tuple {
+            Ty::Tuple(values)
+        } else if fields.is_empty() && keys.len() == 1 {
+            Ty::Map(Box::new(keys.remove(0)), Box::new(values.remove(0)))
+        } else {
+            Ty::Single(self.lexer.src[start..self.lexer.pos].into())
+        })
+    }
+
+    fn parse_function(&mut self, is_async: bool) -> ParseResult<Ty> {
+        self.expect('(')?;
+        self.depth += 1;
+
+        let mut params = Vec::new();
+        while !self.is_next(')')? {
+            params.push(self.parse_param()?);
+            if !self.is_next(')')? {
+                self.expect(',')?;
+            }
+        }
+        self.expect(')')?;
+        self.depth -= 1;
+
+        let mut returns = Vec::new();
+        if self.is_next(':')? {
+            self.lexer.next()?;
+            returns.push(self.parse_type()?);
+            while self.depth == 0 && self.is_next(',')? {
+                self.lexer.next()?;
+                returns.push(self.parse_type()?);
+            }
+        }
+
+        Ok(Ty::Function {
+            params,
+            returns,
+            is_async,
+        })
+    }
+
+    /// Function params are either `name: type`, `name?: type`, `...: type`, or only a type
+    fn parse_param(&mut self) -> ParseResult<(Option<String>, Ty)> {
+        let mut lookahead = self.lexer.clone();
+        let name = match lookahead.next()? {
+            Some(Token::Ellipsis) => Some("..."),
+            Some(Token::Ident(name)) => Some(name),
+            _ => None,
+        };
+
+        let optional = lookahead.peek()? == Some(Token::Punct('?'));
+        if optional {
+            lookahead.next()?;
+        }
+
+        match name {
+            Some(name) if lookahead.peek()? == Some(Token::Punct(':')) => {
+                lookahead.next()?;
+                self.lexer = lookahead;
+                let mut ty = self.parse_type()?;
+                if optional {
+                    ty = Ty::Optional(Box::new(ty));
+                }
+                Ok((Some(name.into()), ty))
+            }
+            Some("...") => {
+                self.lexer.next()?;
+                Ok((Some("...".into()), Ty::Single("any".into())))
+            }
+            _ => Ok((None, self.parse_type()?)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn single(name: &str) -> Ty {
+        Ty::Single(name.into())
+    }
+
+    fn reference(name: &str) -> Ty {
+        Ty::Ref(name.into())
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(parse("string"), Ok(single("string")));
+        assert_eq!(parse("Player"), Ok(reference("Player")));
+        assert_eq!(parse("app.Player"), Ok(reference("app.Player")));
+        assert_eq!(parse("'idle'"), Ok(Ty::LiteralString("idle".into())));
+        assert_eq!(parse("-1.5"), Ok(single("-1.5")));
+    }
+
+    #[test]
+    fn postfix_and_unions() {
+        assert_eq!(
+            parse("string[]?"),
+            Ok(Ty::Optional(Box::new(Ty::Array(Box::new(single(
+                "string"
+            ))))))
+        );
+        assert_eq!(
+            parse("(string|integer)[]"),
+            Ok(Ty::Array(Box::new(Ty::Union(vec![
+                single("string"),
+                single("integer")
+            ]))))
+        );
+        assert_eq!(
+            parse("...string"),
+            Ok(Ty::Variadic(Box::new(single("string"))))
+        );
+        assert_eq!(parse("..."), Ok(Ty::Variadic(Box::new(single("any")))));
+    }
+
+    #[test]
+    fn tables() {
+        assert_eq!(
+            parse("table<string, Player>"),
+            Ok(Ty::Map(
+                Box::new(single("string")),
+                Box::new(reference("Player"))
+            ))
+        );
+        assert_eq!(
+            parse("{ [string]: integer }"),
+            Ok(Ty::Map(
+                Box::new(single("string")),
+                Box::new(single("integer"))
+            ))
+        );
+        assert_eq!(
+            parse("{ [1]: string, [2]: integer }"),
+            Ok(Ty::Tuple(vec![single("string"), single("integer")]))
+        );
+        assert_eq!(
+            parse("{ name: string; tags?: string[] }"),
+            Ok(Ty::Struct(vec![
+                ("name".into(), single("string")),
+                (
+                    "tags".into(),
+                    Ty::Optional(Box::new(Ty::Array(Box::new(single("string")))))
+                ),
+            ]))
+        );
+        assert_eq!(parse("Array<string>"), Ok(single("Array<string>")));
+    }
+
+    #[test]
+    fn functions() {
+        assert_eq!(
+            parse("async fun(evt: Event, ctx?: Ctx, ...: any): boolean, string"),
+            Ok(Ty::Function {
+                params: vec![
+                    (Some("evt".into()), reference("Event")),
+                    (Some("ctx".into()), Ty::Optional(Box::new(reference("Ctx")))),
+                    (Some("...".into()), single("any")),
+                ],
+                returns: vec![single("boolean"), single("string")],
+                is_async: true,
+            })
+        );
+        // Commas inside of brackets belong to the enclosing type, not the function's returns
+        assert_eq!(
+            parse("table<fun(): string, integer>"),
+            Ok(Ty::Map(
+                Box::new(Ty::Function {
+                    params: vec![],
+                    returns: vec![single("string")],
+                    is_async: false,
+                }),
+                Box::new(single("integer"))
+            ))
+        );
+        assert_eq!(
+            parse("fun(string, integer)"),
+            Ok(Ty::Function {
+                params: vec![(None, single("string")), (None, single("integer"))],
+                returns: vec![],
+                is_async: false,
+            })
+        );
+    }
+
+    #[test]
+    fn prefix() {
+        assert_eq!(
+            parse_prefix("string|nil the player's name"),
+            Ok((
+                Ty::Union(vec![single("string"), single("nil")]),
+                " the player's name"
+            ))
+        );
+    }
+
+    #[test]
+    fn malformed() {
+        for (src, err) in [
+            ("", "expected a type but reached the end of type ``"),
+            (
+                "string integer",
+                "unexpected `integer` in type `string integer`",
+            ),
+            (
+                "string|",
+                "expected a type but reached the end of type `string|`",
+            ),
+            (
+                "table<string",
+                "expected `>` but reached the end of type `table<string`",
+            ),
+            (
+                "(string",
+                "expected `)` but reached the end of type `(string`",
+            ),
+            (
+                "{ name string }",
+                "expected `:` but found `string` in type `{ name string }`",
+            ),
+            (
+                "{ name: string",
+                "expected `}` but reached the end of type `{ name: string`",
+            ),
+            (
+                "{ 1: string }",
+                "expected a table key but found `1` in type `{ 1: string }`",
+            ),
+            (
+                "{ a: string, a: integer }",
+                "duplicate table key `a` in type `{ a: string, a: integer }`",
+            ),
+            (
+                "fun(a: string",
+                "expected `,` but reached the end of type `fun(a: string`",
+            ),
+            ("'idle", "unterminated string literal `'idle`"),
+        ] {
+            assert_eq!(parse(src), Err(err.to_string()), "{src}");
+        }
+    }
+}
@@ -0,0 +1,224 @@
+use std::{collections::BTreeMap, path::Path};
+
+use mlua::{Function, IntoLuaMulti, Lua, MultiValue, Table};
+
+use super::searcher_message;
+
+/// Signature at the start of precompiled lua bytecode
+const BYTECODE_SIGNATURE: &[u8] = b"\x1bLua";
+
+/// Lua source or precompiled bytecode for a single embedded module
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmbeddedModule {
+    /// Chunk name used in error messages and tracebacks without the leading `@`
+    pub chunk_name: String,
+    /// Lua source or bytecode. Bytecode is detected from the chunk's signature
+    pub source: Vec<u8>,
+}
+
+/// In memory map of module names to lua sources that `require` can resolve
+///
+/// Installed with [`LuaExtras::add_embedded_modules`][super::LuaExtras::add_embedded_modules] as
+/// a searcher in `package.searchers` (`package.loaders` for lua 5.1 and luajit) right after the
+/// `package.preload` searcher. Sources can be lua text or precompiled bytecode, i.e. from
+/// [`Function::dump`].
+///
+/// **IMPORTANT** Lua refuses to load bytecode unless the lua instance was created with
+/// [`Lua::unsafe_new`], requiring a bytecode module from [`Lua::new`] returns an error.
+///
+/// # Example
+///
+/// ```
+/// use mlua_extras::{mlua::Lua, extras::{EmbeddedModules, LuaExtras}};
+///
+/// let lua = Lua::new();
+/// lua.add_embedded_modules(
+///     EmbeddedModules::new()
+///         // Usually `include_str!("lua/app/util.lua")` or `include_bytes!("lua/app/util.luac")`
+///         .with("app.util", "return { add = function(a, b) return a + b end }")
+/// )?;
+///
+/// lua.load("assert(require('app.util').add(1, 2) == 3)").exec()?;
+/// ```
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct EmbeddedModules {
+    modules: BTreeMap<String, EmbeddedModule>,
+}
+
+impl EmbeddedModules {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Snapshot every `.lua` and `.luac` file in a directory
+    ///
+    /// Module names are the path relative to the directory with `.` as the separator and the
+    /// extension removed. `init` files are named after their parent directory like lua's default
+    /// `?/init.lua` path, i.e. `app/init.lua` is `app` and `app/util.lua` is `app.util`.
+    pub fn from_dir(dir: impl AsRef<Path>) -> mlua::Result<Self> {
+        let mut modules = Self::new();
+        modules.read_dir(dir.as_ref(), &mut Vec::new())?;
+        Ok(modules)
+    }
+
+    fn read_dir(&mut self, dir: &Path, segments: &mut Vec<String>) -> mlua::Result<()> {
+        let mut entries = std::fs::read_dir(dir)
+            .map_err(mlua::Error::external)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(mlua::Error::external)?;
+        entries.sort_by_key(|entry| entry.file_name());
+
+        for entry in entries {
+            let path = entry.path();
+            if path.is_dir() {
+                segments.push(entry.file_name().to_string_lossy().to_string());
+                self.read_dir(&path, segments)?;
+                segments.pop();
+                continue;
+            }
+
+            if !matches!(
+                path.extension().and_then(|ext| ext.to_str()),
+                Some("lua" | "luac")
+            ) {
+                continue;
+            }
+
+            let Some(stem) = path.file_stem().map(|stem| stem.to_string_lossy().to_string())
+            else {
+                continue;
+            };
+            let file_name = entry.file_name().to_string_lossy().to_string();
+
+            let mut name = segments.clone();
+            if stem != "init" || segments.is_empty() {
+                name.push(stem);
+            }
+
+            let chunk_name = segments
+                .iter()
+                .map(String::as_str)
+                .chain([file_name.as_str()])
+                .collect::<Vec<_>>()
+                .join("/");
+
+            let source = std::fs::read(&path).map_err(mlua::Error::external)?;
+            self.insert_with_chunk_name(name.join("."), chunk_name, source);
+        }
+        Ok(())
+    }
+
+    /// Add a module with the source or bytecode, replacing any module with the same name
+    ///
+    /// The chunk name is derived from the module name, i.e. `app.util` is `app/util.lua`
+    pub fn insert(&mut self, name: impl Into<String>, source: impl Into<Vec<u8>>) {
+        let name = name.into();
+        let chunk_name = format!("{}.lua", name.replace('.', "/"));
+        self.insert_with_chunk_name(name, chunk_name, source);
+    }
+
+    /// Same as [`insert`][EmbeddedModules::insert] with an explicit chunk name for tracebacks
+    pub fn insert_with_chunk_name(
+        &mut self,
+        name: impl Into<String>,
+        chunk_name: impl Into<String>,
+        source: impl Into<Vec<u8>>,
+    ) {
+        self.modules.insert(
+            name.into(),
+            EmbeddedModule {
+                chunk_name: chunk_name.into(),
+                source: source.into(),
+            },
+        );
+    }
+
+    /// Add a module with the source or bytecode
+    pub fn with(mut self, name: impl Into<String>, source: impl Into<Vec<u8>>) -> Self {
+        self.insert(name, source);
+        self
+    }
+
+    /// Add a module with the source or bytecode and an explicit chunk name for tracebacks
+    pub fn with_chunk_name(
+        mut self,
+        name: impl Into<String>,
+        chunk_name: impl Into<String>,
+        source: impl Into<Vec<u8>>,
+    ) -> Self {
+        self.insert_with_chunk_name(name, chunk_name, source);
+        self
+    }
+
+    /// Remove a module returning it if it existed
+    pub fn remove(&mut self, name: impl AsRef<str>) -> Option<EmbeddedModule> {
+        self.modules.remove(name.as_ref())
+    }
+
+    pub fn get(&self, name: impl AsRef<str>) -> Option<&EmbeddedModule> {
+        self.modules.get(name.as_ref())
+    }
+
+    pub fn contains(&self, name: impl AsRef<str>) -> bool {
+        self.modules.contains_key(name.as_ref())
+    }
+
+    /// Names of all embedded modules
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.modules.keys().map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.modules.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.modules.is_empty()
+    }
+
+    /// Create the searcher function that is added to `package.searchers`
+    ///
+    /// Like lua's own file searcher the loader is the compiled chunk and the chunk name is passed
+    /// to it as the second argument. An optional environment table can be passed after the module
+    /// name to load the chunk with, i.e. by a [`Sandbox`][super::Sandbox].
+    pub fn searcher(self, lua: &Lua) -> mlua::Result<Function> {
+        lua.create_function(
+            move |lua, (name, env): (String, Option<Table>)| -> mlua::Result<MultiValue> {
+                let Some(module) = self.get(&name) else {
+                    return searcher_message(format!("no embedded module '{name}'"))
+                        .into_lua_multi(lua);
+                };
+
+                let mut chunk = lua
+                    .load(module.source.as_slice())
+                    .set_name(format!("@{}", module.chunk_name));
+                if let Some(env) = env {
+                    chunk = chunk.set_environment(env);
+                }
+
+                let loader = chunk.into_function().map_err(|err| {
+                    let hint = if module.source.starts_with(BYTECODE_SIGNATURE) {
+                        " (bytecode can only be loaded by a lua instance created with `Lua::unsafe_new`)"
+                    } else {
+                        ""
+                    };
+                    mlua::Error::runtime(format!(
+                        "error loading module '{name}' from embedded file '{}'{hint}:\n\t{err}",
+                        module.chunk_name
+                    ))
+                })?;
+                (loader, module.chunk_name.clone()).into_lua_multi(lua)
+            },
+        )
+    }
+}
+
+impl<N: Into<String>, S: Into<Vec<u8>>> FromIterator<(N, S)> for EmbeddedModules {
+    fn from_iter<T: IntoIterator<Item = (N, S)>>(iter: T) -> Self {
+        let mut modules = Self::new();
+        for (name, source) in iter {
+            modules.insert(name, source);
+        }
+        modules
+    }
+}
@@ -0,0 +1,193 @@
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+
+use mlua::{HookTriggers, Lua, VmState};
+
+/// Reason an [`ExecutionGuard`] stopped a script
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitExceeded {
+    /// The script ran more instructions than the budget allows
+    Instructions {
+        /// Instruction budget of the guard
+        limit: u64,
+    },
+    /// The script ran longer than the timeout
+    Timeout {
+        /// Timeout of the guard
+        timeout: Duration,
+    },
+}
+
+impl std::fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Instructions { limit } => {
+                write!(f, "script exceeded the instruction limit of {limit}")
+            }
+            Self::Timeout { timeout } => {
+                write!(f, "script exceeded the time limit of {timeout:?}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for LimitExceeded {}
+
+impl LimitExceeded {
+    /// Find the limit that stopped a script in an error
+    ///
+    /// The limit is raised as a lua error so it may be wrapped by callbacks and context on its
+    /// way back to rust
+    pub fn find(error: &mlua::Error) -> Option<LimitExceeded> {
+        error
+            .chain()
+            .find_map(|err| err.downcast_ref::<LimitExceeded>())
+            .copied()
+    }
+}
+
+/// Marks that a hook of an [`ExecutionGuard`] or a sampling
+/// [`MemoryTracker`][super::MemoryTracker] is installed
+struct ActiveHook;
+
+/// Claims the lua hook for a run and removes it when dropped so it is also removed if the run
+/// panics
+pub(super) struct HookGuard<'lua>(&'lua Lua);
+
+impl<'lua> HookGuard<'lua> {
+    /// Claim the hook before it is set, failing if another run already owns it
+    ///
+    /// Lua only has a single hook, so replacing the hook of an enclosing run would lift its
+    /// limits for the rest of that run.
+    pub(super) fn acquire(lua: &'lua Lua) -> mlua::Result<Self> {
+        if lua.app_data_ref::<ActiveHook>().is_some() {
+            return Err(mlua::Error::runtime(
+                "a lua hook from an `ExecutionGuard` or a sampling `MemoryTracker` is already active",
+            ));
+        }
+        lua.set_app_data(ActiveHook);
+        Ok(Self(lua))
+    }
+}
+
+impl Drop for HookGuard<'_> {
+    fn drop(&mut self) {
+        self.0.remove_hook();
+        self.0.remove_app_data::<ActiveHook>();
+    }
+}
+
+/// Stops scripts that run too many instructions or for too long
+///
+/// The limits are enforced with a lua hook that is installed for the duration of
+/// [`run`][ExecutionGuard::run] and the counters start over for each run. Scripts that catch the
+/// error with `pcall` are stopped again the next time the hook runs.
+///
+/// **IMPORTANT** Lua only has a single hook, so guards can't be nested and a run inside another
+/// guard's run, or inside a [sampling][super::MemoryTracker::sample] tracker's run, returns an
+/// error instead of lifting the outer limits. mlua can't read the current hook, so a hook the
+/// host set itself is replaced and is *not* restored afterwards. Set it again after the run if it
+/// is still needed.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use mlua_extras::{mlua::Lua, extras::{ExecutionGuard, LimitExceeded, LuaExtras}};
+///
+/// let lua = Lua::new();
+/// let sandbox = lua.new_sandbox()?.finish()?;
+///
+/// let guard = ExecutionGuard::new()
+///     .instructions(1_000_000)
+///     .timeout(Duration::from_millis(100));
+///
+/// let err = guard.run(&lua, || sandbox.exec("spin", "while true do end")).unwrap_err();
+/// assert!(matches!(err.downcast_ref::<LimitExceeded>(), Some(LimitExceeded::Instructions { .. })));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExecutionGuard {
+    instructions: Option<u64>,
+    timeout: Option<Duration>,
+    interval: u32,
+}
+
+impl Default for ExecutionGuard {
+    fn default() -> Self {
+        Self {
+            instructions: None,
+            timeout: None,
+            interval: 1000,
+        }
+    }
+}
+
+impl ExecutionGuard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maximum number of instructions a run may execute
+    pub fn instructions(mut self, limit: u64) -> Self {
+        self.instructions = Some(limit);
+        self
+    }
+
+    /// Maximum time a run may take
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Number of instructions between each check of the limits: Default `1000`
+    ///
+    /// Smaller intervals are more precise but slow down the script
+    pub fn interval(mut self, interval: u32) -> Self {
+        self.interval = interval.max(1);
+        self
+    }
+
+    /// Run the function with the limits enforced on any lua code it calls
+    ///
+    /// If a limit is exceeded the returned error is an [`ExternalError`][mlua::Error::ExternalError]
+    /// of [`LimitExceeded`] no matter how deeply it was wrapped. Fails without running the
+    /// function if a hook of another guard or sampling tracker is already active.
+    pub fn run<R>(&self, lua: &Lua, f: impl FnOnce() -> mlua::Result<R>) -> mlua::Result<R> {
+        if self.instructions.is_none() && self.timeout.is_none() {
+            return f();
+        }
+
+        let instructions = self.instructions;
+        let timeout = self.timeout;
+        let interval = self.interval as u64;
+        let start = Instant::now();
+        let count = AtomicU64::new(0);
+
+        let _hook = HookGuard::acquire(lua)?;
+        lua.set_hook(
+            HookTriggers {
+                every_nth_instruction: Some(self.interval),
+                ..Default::default()
+            },
+            move |_lua, _debug| {
+                let count = count.fetch_add(interval, Ordering::Relaxed) + interval;
+                if let Some(limit) = instructions.filter(|limit| count > *limit) {
+                    return Err(mlua::Error::external(LimitExceeded::Instructions { limit }));
+                }
+                if let Some(timeout) = timeout.filter(|timeout| start.elapsed() > *timeout) {
+                    return Err(mlua::Error::external(LimitExceeded::Timeout { timeout }));
+                }
+                Ok(VmState::Continue)
+            },
+        )?;
+
+        let result = f();
+
+        result.map_err(|err| match LimitExceeded::find(&err) {
+            Some(limit) => mlua::Error::external(limit),
+            None => err,
+        })
+    }
+}
@@ -22,9 +22,8 @@
 /// }
 /// ```
 ///
-/// It can also be used to asssign functions to nested tables. This requires the `LuaExtras` crate
-/// when you start with lua as the source, and the `Require` trait when using any other table as
-/// the source.
+/// It can also be used to asssign functions to nested tables starting from lua's globals or any
+/// other table. Missing tables along the path are created.
 ///
 /// ```
 /// use mlua::{Lua, Table};
@@ -83,7 +82,7 @@ macro_rules! function {
         }
     } => {
         {
-            match $source.require::<mlua::Table>(stringify!($($inner.)*)) $(-> $ret: ty)? {
+            match $crate::extras::NestedSource::nested_table(&$source, &$lua, stringify!($($inner.)*)) {
                 Ok(table) => match $lua.create_function(|$l$(: $lty)?, ($($arg,)*): ($($aty,)*)| $(-> $ret)? {
                     $($body)*
                 }) {
@@ -0,0 +1,226 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use mlua::Lua;
+#[cfg(not(feature = "luau"))]
+use mlua::{HookTriggers, VmState};
+
+#[cfg(not(feature = "luau"))]
+use super::guard::HookGuard;
+
+/// A script allocated more memory than its [`MemoryTracker`] allows
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryLimitExceeded {
+    /// Name of the chunk that was running
+    pub chunk: String,
+    /// Limit of the tracker in bytes
+    pub limit: usize,
+    /// Bytes the tracker's scripts were already holding before the chunk ran
+    pub retained: usize,
+}
+
+impl std::fmt::Display for MemoryLimitExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "memory limit of {} bytes exceeded while running '{}' ({} bytes already in use)",
+            self.limit, self.chunk, self.retained
+        )
+    }
+}
+
+impl std::error::Error for MemoryLimitExceeded {}
+
+/// Memory statistics recorded by a [`MemoryTracker`]
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryUsage {
+    /// Bytes still held after all runs, i.e. globals and modules of the environment
+    pub retained: usize,
+    /// Most bytes held during a run when [sampled][MemoryTracker::sample], otherwise at the end
+    /// of a run or when the limit was reached
+    pub peak: usize,
+    /// Change in the bytes held by the most recent run, negative if it freed retained memory
+    pub last_run: isize,
+    /// Number of runs
+    pub runs: u64,
+    /// Number of runs that exceeded the limit
+    pub exceeded: u64,
+}
+
+/// Accounts for the memory used by the scripts of a single plugin or environment
+///
+/// Memory allocated by a run that is not freed when it finishes is retained by the tracker and
+/// counts towards the limit of the following runs. The limit is enforced with
+/// [`Lua::set_memory_limit`] while a run is active, never raising a lower limit that was already
+/// set, and the previous limit is restored afterwards.
+///
+/// A full garbage collection is done before and after each run so only live memory is measured.
+/// Lua's allocator is shared so the accounting is still approximate, memory freed by other
+/// scripts during a run lowers the measured usage.
+///
+/// # Example
+///
+/// ```
+/// use mlua_extras::{mlua::Lua, extras::{LuaExtras, MemoryLimitExceeded, MemoryTracker}};
+///
+/// let lua = Lua::new();
+/// let sandbox = lua.new_sandbox()?.finish()?;
+/// let mut plugin = MemoryTracker::new().limit(1024 * 1024);
+///
+/// plugin.run(&lua, "plugin/init.lua", || sandbox.exec("plugin/init.lua", "cache = {}"))?;
+///
+/// let err = plugin
+///     .run(&lua, "plugin/load.lua", || sandbox.exec("plugin/load.lua", "cache[1] = string.rep('x', 2 ^ 24)"))
+///     .unwrap_err();
+/// assert_eq!(err.downcast_ref::<MemoryLimitExceeded>().unwrap().chunk, "plugin/load.lua");
+/// println!("peak: {} bytes", plugin.usage().peak);
+/// ```
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct MemoryTracker {
+    limit: Option<usize>,
+    #[cfg(not(feature = "luau"))]
+    sample: Option<u32>,
+    usage: MemoryUsage,
+}
+
+impl MemoryTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maximum number of bytes the tracked scripts may hold
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Sample the memory in use every `interval` instructions to record the peak during a run
+    ///
+    /// **IMPORTANT** This uses the lua hook, so a sampled run inside an
+    /// [`ExecutionGuard`][super::ExecutionGuard]'s run, or the other way around, returns an error
+    /// instead of replacing the other hook. A hook the host set itself is replaced and is *not*
+    /// restored afterwards.
+    #[cfg(not(feature = "luau"))]
+    pub fn sample(mut self, interval: u32) -> Self {
+        self.sample = Some(interval.max(1));
+        self
+    }
+
+    /// Get the memory limit in bytes
+    pub fn get_limit(&self) -> Option<usize> {
+        self.limit
+    }
+
+    /// Statistics of all runs so far
+    pub fn usage(&self) -> MemoryUsage {
+        self.usage
+    }
+
+    /// Forget the memory retained by previous runs, i.e. after the environment is dropped
+    pub fn reset(&mut self) {
+        self.usage = MemoryUsage::default();
+    }
+
+    /// Run the function counting the memory it allocates towards this tracker
+    ///
+    /// If the limit is reached the returned error is an
+    /// [`ExternalError`][mlua::Error::ExternalError] of [`MemoryLimitExceeded`] with the chunk
+    /// name. Fails without running the function if it is [sampled][MemoryTracker::sample] while
+    /// another hook of this crate is active.
+    pub fn run<R>(
+        &mut self,
+        lua: &Lua,
+        chunk: impl AsRef<str>,
+        f: impl FnOnce() -> mlua::Result<R>,
+    ) -> mlua::Result<R> {
+        lua.gc_collect()?;
+        let before = lua.used_memory();
+
+        let _limit = match self.limit {
+            Some(limit) => Some(RestoreLimit::new(
+                lua,
+                before + limit.saturating_sub(self.usage.retained),
+            )?),
+            None => None,
+        };
+
+        let peak = Arc::new(AtomicUsize::new(before));
+        #[cfg(not(feature = "luau"))]
+        let _hook = match self.sample {
+            Some(interval) => {
+                let hook = HookGuard::acquire(lua)?;
+                let peak = peak.clone();
+                lua.set_hook(
+                    HookTriggers {
+                        every_nth_instruction: Some(interval),
+                        ..Default::default()
+                    },
+                    move |lua, _debug| {
+                        peak.fetch_max(lua.used_memory(), Ordering::Relaxed);
+                        Ok(VmState::Continue)
+                    },
+                )?;
+                Some(hook)
+            }
+            None => None,
+        };
+
+        let result = f();
+
+        lua.gc_collect()?;
+        let after = lua.used_memory();
+        let held = |used: usize| (self.usage.retained + used).saturating_sub(before);
+
+        self.usage.runs += 1;
+        self.usage.last_run = after as isize - before as isize;
+        self.usage.peak = self
+            .usage
+            .peak
+            .max(held(peak.load(Ordering::Relaxed)))
+            .max(held(after));
+        self.usage.retained = held(after);
+
+        match (result, self.limit) {
+            (Err(err), Some(limit))
+                if err.chain().any(|err| {
+                    matches!(err.downcast_ref::<mlua::Error>(), Some(mlua::Error::MemoryError(_)))
+                }) =>
+            {
+                self.usage.exceeded += 1;
+                self.usage.peak = self.usage.peak.max(limit);
+                Err(mlua::Error::external(MemoryLimitExceeded {
+                    chunk: chunk.as_ref().to_string(),
+                    limit,
+                    retained: self.usage.retained,
+                }))
+            }
+            (result, _) => result,
+        }
+    }
+}
+
+/// Sets the memory limit for a run and restores the previous limit when dropped so it is also
+/// restored if the run panics
+struct RestoreLimit<'lua> {
+    lua: &'lua Lua,
+    previous: usize,
+}
+
+impl<'lua> RestoreLimit<'lua> {
+    /// Lower the limit to `limit` unless the previous limit is already lower. `0` is no limit
+    fn new(lua: &'lua Lua, limit: usize) -> mlua::Result<Self> {
+        let previous = lua.set_memory_limit(limit)?;
+        if previous != 0 && previous < limit {
+            lua.set_memory_limit(previous)?;
+        }
+        Ok(Self { lua, previous })
+    }
+}
+
+impl Drop for RestoreLimit<'_> {
+    fn drop(&mut self) {
+        let _ = self.lua.set_memory_limit(self.previous);
+    }
+}
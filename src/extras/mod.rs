@@ -1,13 +1,35 @@
 use std::path::Path;
 
-use mlua::{AnyUserData, FromLua, FromLuaMulti, IntoLua, IntoLuaMulti, Lua, Table, UserDataFields};
-
+use mlua::{
+    AnyUserData, FromLua, FromLuaMulti, Function, IntoLua, IntoLuaMulti, Lua, MultiValue, Table,
+    UserDataFields, Value,
+};
+
+mod embedded;
+#[cfg(not(feature = "luau"))]
+mod guard;
 mod macros;
+mod memory;
 mod module;
+mod package_path;
+mod reload;
 mod require;
+mod sandbox;
+mod searcher;
 
+pub use embedded::{EmbeddedModule, EmbeddedModules};
+#[cfg(not(feature = "luau"))]
+pub use guard::{ExecutionGuard, LimitExceeded};
+pub use memory::{MemoryLimitExceeded, MemoryTracker, MemoryUsage};
 pub use module::{LuaModule, Module, ModuleBuilder, ModuleFields, ModuleMethods};
+use module::ModulePaths;
+pub use package_path::PackagePath;
+pub use reload::{HotReloader, ReloadReport, TrackedModule};
 pub use require::Require;
+#[doc(hidden)]
+pub use require::NestedSource;
+pub use sandbox::{Capability, Sandbox, SandboxBuilder};
+pub use searcher::SearchResult;
 
 use crate::MaybeSend;
 
@@ -18,6 +40,10 @@ use crate::MaybeSend;
 /// - Shorthand for `lua.globals().set` that include adding any value and adding rust functions
 ///     skipping [`create_function`][mlua::Lua::create_function]
 /// - A `require` method that is similar to the [`Require`] traits. Allows for `lua` style [`require`](https://www.lua.org/manual/5.1/manual.html#pdf-require)
+/// - [`EmbeddedModules`] that lua's `require` can load without the sources being on disk
+/// - Rust functions as `require` searchers
+/// - Modules that are only built when they are first required
+/// - [`Sandbox`] environments for untrusted scripts
 pub trait LuaExtras {
     /// Get the `package.path` value
     ///
@@ -136,6 +162,34 @@ pub trait LuaExtras {
     ///   - <https://www.lua.org/manual/5.4/manual.html#pdf-package.searchpath>
     fn set_cpaths<S: AsRef<Path>>(&self, paths: impl IntoIterator<Item = S>) -> mlua::Result<()>;
 
+    /// Get the `package.path` value as a list of templates
+    fn package_path(&self) -> mlua::Result<PackagePath>;
+
+    /// Get the `package.cpath` value as a list of templates
+    fn package_cpath(&self) -> mlua::Result<PackagePath>;
+
+    /// Replace the `package.path` value
+    fn set_package_path(&self, path: &PackagePath) -> mlua::Result<()>;
+
+    /// Replace the `package.cpath` value
+    fn set_package_cpath(&self, cpath: &PackagePath) -> mlua::Result<()>;
+
+    /// Modify the `package.path` templates
+    ///
+    /// The value is only written back once the update succeeds, so an error leaves
+    /// `package.path` unchanged
+    fn update_path<F>(&self, update: F) -> mlua::Result<()>
+    where
+        F: FnOnce(&mut PackagePath) -> mlua::Result<()>;
+
+    /// Modify the `package.cpath` templates
+    ///
+    /// The value is only written back once the update succeeds, so an error leaves
+    /// `package.cpath` unchanged
+    fn update_cpath<F>(&self, update: F) -> mlua::Result<()>
+    where
+        F: FnOnce(&mut PackagePath) -> mlua::Result<()>;
+
     /// Set a global variable
     fn set_global<K, V>(&self, key: K, value: V) -> mlua::Result<()>
     where
@@ -151,6 +205,97 @@ pub trait LuaExtras {
 
     /// Fetch a nested lua value starting from lua's globals
     fn require<R: FromLua>(&self, path: impl AsRef<str>) -> mlua::Result<R>;
+
+    /// Fetch a nested table starting from lua's globals creating any missing tables along the path
+    ///
+    /// Returns an error if a value along the path is not a table
+    fn ensure_table(&self, path: impl AsRef<str>) -> mlua::Result<Table>;
+
+    /// Set a nested value starting from lua's globals creating any missing tables along the path
+    fn set_nested<V: IntoLua>(&self, path: impl AsRef<str>, value: V) -> mlua::Result<()>;
+
+    /// Remove a nested value starting from lua's globals returning whether it existed
+    fn remove_nested(&self, path: impl AsRef<str>) -> mlua::Result<bool>;
+
+    /// Check if a nested value starting from lua's globals exists and is not `nil`
+    fn exists(&self, path: impl AsRef<str>) -> mlua::Result<bool>;
+
+    /// Add a searcher that resolves `require` calls from the in memory modules
+    ///
+    /// The searcher is inserted right after the `package.preload` searcher so embedded modules
+    /// take priority over files found with `package.path` and `package.cpath`.
+    fn add_embedded_modules(&self, modules: EmbeddedModules) -> mlua::Result<()>;
+
+    /// Add a searcher to the start of `package.searchers` (`package.loaders` for lua 5.1 and luajit)
+    ///
+    /// The searcher is called with the name passed to `require` and runs before all other
+    /// searchers, including `package.preload`.
+    /// see:
+    ///   - <https://www.lua.org/manual/5.4/manual.html#pdf-package.searchers>
+    fn prepend_searcher<F>(&self, searcher: F) -> mlua::Result<()>
+    where
+        F: Fn(&Lua, &str) -> mlua::Result<SearchResult> + MaybeSend + 'static;
+
+    /// Add a searcher to the end of `package.searchers` (`package.loaders` for lua 5.1 and luajit)
+    ///
+    /// The searcher is called with the name passed to `require` only when no other searcher
+    /// found the module.
+    /// see:
+    ///   - <https://www.lua.org/manual/5.4/manual.html#pdf-package.searchers>
+    fn append_searcher<F>(&self, searcher: F) -> mlua::Result<()>
+    where
+        F: Fn(&Lua, &str) -> mlua::Result<SearchResult> + MaybeSend + 'static;
+
+    /// Register a module in `package.preload` so it is only built when it is first required
+    ///
+    /// Nested modules added with [`add_module`][ModuleFields::add_module] can also be required by
+    /// their dotted path, i.e. `require("app.util")`, which returns the same table as
+    /// `require("app").util`. Use
+    /// [`DefinitionsBuilder::define_module`][crate::typed::generator::DefinitionsBuilder::define_module]
+    /// to generate matching definitions for a [`TypedModule`][crate::typed::TypedModule].
+    ///
+    /// **IMPORTANT** To find the nested modules `M::add_fields` and `M::add_methods` are called
+    /// once when the module is registered. Only the `add_module` calls are recorded but the values
+    /// passed to the other calls are still created, so expensive setup should happen inside of
+    /// the module's functions instead.
+    /// see:
+    ///   - <https://www.lua.org/manual/5.4/manual.html#pdf-package.preload>
+    fn preload_module<M: Module + 'static>(&self, name: impl AsRef<str>) -> mlua::Result<()>;
+
+    /// Start building an isolated environment for running untrusted scripts
+    ///
+    /// Same as [`SandboxBuilder::new`]. Not named `sandbox` since luau's inherent
+    /// `Lua::sandbox` would take precedence over it.
+    fn new_sandbox(&self) -> mlua::Result<SandboxBuilder<'_>>;
+}
+
+/// Get `package.searchers`, or `package.loaders` for lua 5.1 and luajit
+fn package_searchers(lua: &Lua) -> mlua::Result<Table> {
+    let package = lua.globals().get::<Table>("package")?;
+    match package.get::<Option<Table>>("searchers")? {
+        Some(searchers) => Ok(searchers),
+        None => package.get::<Table>("loaders"),
+    }
+}
+
+/// Add a searcher right after the `package.preload` searcher
+fn insert_searcher(lua: &Lua, searcher: Function) -> mlua::Result<()> {
+    let searchers = package_searchers(lua)?;
+    // Index 1 is the `package.preload` searcher
+    let index = searchers.raw_len().min(1) + 1;
+    searchers.raw_insert(index as i64, searcher)
+}
+
+/// Format the message a searcher returns when it can't find a module
+///
+/// Lua 5.4 separates the messages from each searcher itself while older versions expect each
+/// message to start with `\n\t`.
+fn searcher_message(message: impl std::fmt::Display) -> String {
+    if cfg!(feature = "lua54") {
+        message.to_string()
+    } else {
+        format!("\n\t{message}")
+    }
 }
 
 impl LuaExtras for Lua {
@@ -185,171 +330,196 @@ impl LuaExtras for Lua {
     }
 
     fn set_path<S: AsRef<Path>>(&self, path: S) -> mlua::Result<()> {
-        self.globals()
-            .get::<Table>("package")
-            .unwrap()
-            .set("path", path.as_ref().display().to_string())
+        self.set_package_path(&PackagePath::parse(&path.as_ref().display().to_string()))
     }
 
     fn set_paths<S: AsRef<Path>>(&self, paths: impl IntoIterator<Item = S>) -> mlua::Result<()> {
-        self.globals().get::<Table>("package").unwrap().set(
-            "path",
-            paths
-                .into_iter()
-                .map(|s| s.as_ref().display().to_string())
-                .collect::<Vec<_>>()
-                .join(";"),
-        )
+        self.set_package_path(&paths.into_iter().collect())
     }
 
     fn prepend_path<S: AsRef<Path>>(&self, path: S) -> mlua::Result<()> {
-        let lua_path = match self.path()?.trim() {
-            "" => path.as_ref().display().to_string(),
-            other => format!("{};{other}", path.as_ref().display()),
-        };
-        self.globals()
-            .get::<Table>("package")?
-            .set("path", lua_path)
+        self.update_path(|lua_path| {
+            lua_path.prepend(path);
+            Ok(())
+        })
     }
 
     fn prepend_paths<S: AsRef<Path>>(
         &self,
         paths: impl IntoIterator<Item = S>,
     ) -> mlua::Result<()> {
-        let new = paths
-            .into_iter()
-            .map(|v| v.as_ref().display().to_string())
-            .collect::<Vec<_>>()
-            .join(";");
-        let lua_path = match self.path()?.trim() {
-            "" => new,
-            other => format!("{new};{other}"),
-        };
-        self.globals()
-            .get::<Table>("package")?
-            .set("path", lua_path)
+        self.update_path(|lua_path| {
+            for (i, path) in paths.into_iter().enumerate() {
+                lua_path.insert(i, path);
+            }
+            Ok(())
+        })
     }
 
     fn append_path<S: AsRef<Path>>(&self, path: S) -> mlua::Result<()> {
-        let lua_path = match self.path()?.trim() {
-            "" => path.as_ref().display().to_string(),
-            other => format!("{other};{}", path.as_ref().display()),
-        };
-        self.globals()
-            .get::<Table>("package")?
-            .set("path", lua_path)
+        self.update_path(|lua_path| {
+            lua_path.append(path);
+            Ok(())
+        })
     }
 
     fn append_paths<S: AsRef<Path>>(&self, paths: impl IntoIterator<Item = S>) -> mlua::Result<()> {
-        let new = paths
-            .into_iter()
-            .map(|v| v.as_ref().display().to_string())
-            .collect::<Vec<_>>()
-            .join(";");
-        let lua_path = match self.path()?.trim() {
-            "" => new,
-            other => format!("{other};{new}"),
-        };
-        self.globals()
-            .get::<Table>("package")?
-            .set("path", lua_path)
+        self.update_path(|lua_path| {
+            for path in paths {
+                lua_path.append(path);
+            }
+            Ok(())
+        })
     }
 
     fn set_cpath<S: AsRef<Path>>(&self, path: S) -> mlua::Result<()> {
-        self.globals()
-            .get::<Table>("package")
-            .unwrap()
-            .set("cpath", path.as_ref().display().to_string())
+        self.set_package_cpath(&PackagePath::parse(&path.as_ref().display().to_string()))
     }
 
     fn set_cpaths<S: AsRef<Path>>(&self, paths: impl IntoIterator<Item = S>) -> mlua::Result<()> {
-        self.globals().get::<Table>("package").unwrap().set(
-            "cpath",
-            paths
-                .into_iter()
-                .map(|s| s.as_ref().display().to_string())
-                .collect::<Vec<_>>()
-                .join(";"),
-        )
+        self.set_package_cpath(&paths.into_iter().collect())
     }
 
     fn prepend_cpath<S: AsRef<Path>>(&self, path: S) -> mlua::Result<()> {
-        let lua_path = match self.path()?.trim() {
-            "" => path.as_ref().display().to_string(),
-            other => format!("{};{other}", path.as_ref().display()),
-        };
-        self.globals()
-            .get::<Table>("package")?
-            .set("cpath", lua_path)
+        self.update_cpath(|lua_cpath| {
+            lua_cpath.prepend(path);
+            Ok(())
+        })
     }
 
     fn prepend_cpaths<S: AsRef<Path>>(
         &self,
         paths: impl IntoIterator<Item = S>,
     ) -> mlua::Result<()> {
-        let new = paths
-            .into_iter()
-            .map(|v| v.as_ref().display().to_string())
-            .collect::<Vec<_>>()
-            .join(";");
-        let lua_path = match self.path()?.trim() {
-            "" => new,
-            other => format!("{new};{other}"),
-        };
-        self.globals()
-            .get::<Table>("package")?
-            .set("cpath", lua_path)
+        self.update_cpath(|lua_cpath| {
+            for (i, path) in paths.into_iter().enumerate() {
+                lua_cpath.insert(i, path);
+            }
+            Ok(())
+        })
     }
 
     fn append_cpath<S: AsRef<Path>>(&self, path: S) -> mlua::Result<()> {
-        let lua_path = match self.path()?.trim() {
-            "" => path.as_ref().display().to_string(),
-            other => format!("{other};{}", path.as_ref().display()),
-        };
-        self.globals()
-            .get::<Table>("package")?
-            .set("cpath", lua_path)
+        self.update_cpath(|lua_cpath| {
+            lua_cpath.append(path);
+            Ok(())
+        })
     }
 
     fn append_cpaths<S: AsRef<Path>>(
         &self,
         paths: impl IntoIterator<Item = S>,
     ) -> mlua::Result<()> {
-        let new = paths
-            .into_iter()
-            .map(|v| v.as_ref().display().to_string())
-            .collect::<Vec<_>>()
-            .join(";");
-        let lua_path = match self.path()?.trim() {
-            "" => new,
-            other => format!("{other};{new}"),
-        };
+        self.update_cpath(|lua_cpath| {
+            for path in paths {
+                lua_cpath.append(path);
+            }
+            Ok(())
+        })
+    }
+
+    fn package_path(&self) -> mlua::Result<PackagePath> {
+        Ok(PackagePath::parse(&self.path()?))
+    }
+
+    fn package_cpath(&self) -> mlua::Result<PackagePath> {
+        Ok(PackagePath::parse(&self.cpath()?))
+    }
+
+    fn set_package_path(&self, path: &PackagePath) -> mlua::Result<()> {
+        self.globals()
+            .get::<Table>("package")?
+            .set("path", path.to_string())
+    }
+
+    fn set_package_cpath(&self, cpath: &PackagePath) -> mlua::Result<()> {
         self.globals()
             .get::<Table>("package")?
-            .set("cpath", lua_path)
+            .set("cpath", cpath.to_string())
+    }
+
+    fn update_path<F>(&self, update: F) -> mlua::Result<()>
+    where
+        F: FnOnce(&mut PackagePath) -> mlua::Result<()>,
+    {
+        let mut path = self.package_path()?;
+        update(&mut path)?;
+        self.set_package_path(&path)
+    }
+
+    fn update_cpath<F>(&self, update: F) -> mlua::Result<()>
+    where
+        F: FnOnce(&mut PackagePath) -> mlua::Result<()>,
+    {
+        let mut cpath = self.package_cpath()?;
+        update(&mut cpath)?;
+        self.set_package_cpath(&cpath)
     }
 
     fn require<R: FromLua>(&self, path: impl AsRef<str>) -> mlua::Result<R> {
-        let segments = path
-            .as_ref()
-            .split('.')
-            .filter_map(|v| (!v.is_empty()).then_some(v.trim()))
-            .collect::<Vec<_>>();
-
-        let mut module = self.globals();
-        if !segments.is_empty() {
-            for seg in &segments[..segments.len() - 1] {
-                module = module.get::<Table>(*seg)?;
-            }
-        }
+        self.globals().require(path)
+    }
+
+    fn ensure_table(&self, path: impl AsRef<str>) -> mlua::Result<Table> {
+        self.globals().ensure_table(self, path)
+    }
+
+    fn set_nested<V: IntoLua>(&self, path: impl AsRef<str>, value: V) -> mlua::Result<()> {
+        self.globals().set_nested(self, path, value)
+    }
+
+    fn remove_nested(&self, path: impl AsRef<str>) -> mlua::Result<bool> {
+        self.globals().remove_nested(path)
+    }
+
+    fn exists(&self, path: impl AsRef<str>) -> mlua::Result<bool> {
+        self.globals().exists(path)
+    }
 
-        match segments.last() {
-            Some(seg) => module.get::<R>(*seg),
-            None => Err(mlua::Error::runtime(format!(
-                "module not found: {:?}",
-                path.as_ref()
-            ))),
+    fn add_embedded_modules(&self, modules: EmbeddedModules) -> mlua::Result<()> {
+        insert_searcher(self, modules.searcher(self)?)
+    }
+
+    fn prepend_searcher<F>(&self, searcher: F) -> mlua::Result<()>
+    where
+        F: Fn(&Lua, &str) -> mlua::Result<SearchResult> + MaybeSend + 'static,
+    {
+        let searcher = self.create_function(move |lua, name: String| searcher(lua, &name))?;
+        package_searchers(self)?.raw_insert(1, searcher)
+    }
+
+    fn append_searcher<F>(&self, searcher: F) -> mlua::Result<()>
+    where
+        F: Fn(&Lua, &str) -> mlua::Result<SearchResult> + MaybeSend + 'static,
+    {
+        let searcher = self.create_function(move |lua, name: String| searcher(lua, &name))?;
+        package_searchers(self)?.raw_push(searcher)
+    }
+
+    fn preload_module<M: Module + 'static>(&self, name: impl AsRef<str>) -> mlua::Result<()> {
+        let preload = self.globals().get::<Table>("package")?.get::<Table>("preload")?;
+        preload.set(
+            name.as_ref(),
+            self.create_function(|lua, _: MultiValue| M::module().into_lua(lua))?,
+        )?;
+
+        for (parent, key) in ModulePaths::new::<M>(self, name.as_ref())?.nested {
+            let path = format!("{parent}.{key}");
+            preload.set(
+                path,
+                self.create_function(move |lua, _: MultiValue| {
+                    lua.globals()
+                        .get::<Function>("require")?
+                        .call::<Table>(parent.as_str())?
+                        .get::<Value>(key.as_str())
+                })?,
+            )?;
         }
+        Ok(())
+    }
+
+    fn new_sandbox(&self) -> mlua::Result<SandboxBuilder<'_>> {
+        SandboxBuilder::new(self)
     }
 }
 
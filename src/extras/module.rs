@@ -1,6 +1,6 @@
 use std::{any::type_name, marker::PhantomData};
 
-use mlua::{FromLuaMulti, IntoLua, IntoLuaMulti};
+use mlua::{FromLua, FromLuaMulti, IntoLua, IntoLuaMulti};
 
 use crate::MaybeSend;
 
@@ -97,6 +97,26 @@ pub trait ModuleMethods {
         F: Fn(&mlua::Lua, mlua::Table, A) -> mlua::Result<R> + MaybeSend + 'static,
         A: FromLuaMulti,
         R: IntoLuaMulti;
+
+    #[cfg(feature = "async")]
+    /// Add an async function to this module's table
+    fn add_async_function<K, F, A, R, FR>(&mut self, name: K, function: F) -> mlua::Result<()>
+    where
+        K: IntoLua,
+        F: Fn(mlua::Lua, A) -> FR + MaybeSend + 'static,
+        A: FromLuaMulti,
+        R: IntoLuaMulti,
+        FR: std::future::Future<Output = mlua::Result<R>> + MaybeSend + 'static;
+
+    #[cfg(feature = "async")]
+    /// Add an async method to this module's table
+    fn add_async_method<K, F, A, R, FR>(&mut self, name: K, function: F) -> mlua::Result<()>
+    where
+        K: IntoLua,
+        F: Fn(mlua::Lua, mlua::Table, A) -> FR + MaybeSend + 'static,
+        A: FromLuaMulti,
+        R: IntoLuaMulti,
+        FR: std::future::Future<Output = mlua::Result<R>> + MaybeSend + 'static;
 }
 
 /// Builder that construct a module based on the [`Module`] trait
@@ -202,8 +222,8 @@ impl<'a> ModuleMethods for ModuleBuilder<'a> {
         self.table.set(
             name,
             self.lua
-                .create_function(move |lua, args: mlua::MultiValue| {
-                    let this = mlua::Table::from_lua_multi(args.clone(), lua)?;
+                .create_function(move |lua, mut args: mlua::MultiValue| {
+                    let this = mlua::Table::from_lua(args.pop_front().unwrap_or(mlua::Value::Nil), lua)?;
                     let rest = A::from_lua_multi(args, lua)?;
                     function(lua, this, rest)
                 })?,
@@ -229,11 +249,198 @@ impl<'a> ModuleMethods for ModuleBuilder<'a> {
         meta.set(
             name,
             self.lua
-                .create_function(move |lua, args: mlua::MultiValue| {
-                    let this = mlua::Table::from_lua_multi(args.clone(), lua)?;
+                .create_function(move |lua, mut args: mlua::MultiValue| {
+                    let this = mlua::Table::from_lua(args.pop_front().unwrap_or(mlua::Value::Nil), lua)?;
                     let rest = A::from_lua_multi(args, lua)?;
                     function(lua, this, rest)
                 })?,
         )
     }
+
+    #[cfg(feature = "async")]
+    fn add_async_function<K, F, A, R, FR>(&mut self, name: K, function: F) -> mlua::Result<()>
+    where
+        K: IntoLua,
+        F: Fn(mlua::Lua, A) -> FR + MaybeSend + 'static,
+        A: FromLuaMulti,
+        R: IntoLuaMulti,
+        FR: std::future::Future<Output = mlua::Result<R>> + MaybeSend + 'static,
+    {
+        self.table.set(name, self.lua.create_async_function(function)?)
+    }
+
+    #[cfg(feature = "async")]
+    fn add_async_method<K, F, A, R, FR>(&mut self, name: K, function: F) -> mlua::Result<()>
+    where
+        K: IntoLua,
+        F: Fn(mlua::Lua, mlua::Table, A) -> FR + MaybeSend + 'static,
+        A: FromLuaMulti,
+        R: IntoLuaMulti,
+        FR: std::future::Future<Output = mlua::Result<R>> + MaybeSend + 'static,
+    {
+        self.table.set(
+            name,
+            self.lua
+                .create_async_function(move |lua, mut args: mlua::MultiValue| {
+                    let fut = mlua::Table::from_lua(args.pop_front().unwrap_or(mlua::Value::Nil), &lua)
+                        .and_then(|this| Ok((this, A::from_lua_multi(args, &lua)?)))
+                        .map(|(this, rest)| function(lua, this, rest));
+                    async move {
+                        match fut {
+                            Ok(fut) => fut.await,
+                            Err(err) => Err(err),
+                        }
+                    }
+                })?,
+        )
+    }
+}
+
+/// Collects the dotted path of every nested module without building any tables
+///
+/// Used to make nested modules requirable by their own path
+pub(crate) struct ModulePaths<'a> {
+    lua: &'a mlua::Lua,
+    path: String,
+    parents: Vec<&'static str>,
+    /// Path of the parent module and the key of the nested module in the parent
+    pub(crate) nested: Vec<(String, String)>,
+}
+
+impl<'a> ModulePaths<'a> {
+    pub(crate) fn new<M: Module>(lua: &'a mlua::Lua, path: impl Into<String>) -> mlua::Result<Self> {
+        let mut paths = ModulePaths {
+            lua,
+            path: path.into(),
+            parents: vec![type_name::<M>()],
+            nested: Vec::new(),
+        };
+
+        M::add_fields(&mut paths)?;
+        M::add_methods(&mut paths)?;
+
+        Ok(paths)
+    }
+}
+
+impl<'a> ModuleFields for ModulePaths<'a> {
+    fn add_field<K, V>(&mut self, _name: K, _value: V) -> mlua::Result<()>
+    where
+        K: IntoLua,
+        V: IntoLua,
+    {
+        Ok(())
+    }
+
+    fn add_meta_field<K, V>(&mut self, _name: K, _value: V) -> mlua::Result<()>
+    where
+        K: IntoLua,
+        V: IntoLua,
+    {
+        Ok(())
+    }
+
+    fn add_module<K, V>(&mut self, name: K) -> mlua::Result<()>
+    where
+        K: IntoLua,
+        V: Module,
+    {
+        if self.parents.contains(&type_name::<V>()) {
+            return Err(mlua::Error::runtime(format!(
+                "infinite nested modules using: '{}'",
+                type_name::<V>()
+            )));
+        }
+
+        // Only string keys can be used as part of a `require` path
+        let mlua::Value::String(key) = name.into_lua(self.lua)? else {
+            return Ok(());
+        };
+        let key = key.to_str()?.to_string();
+
+        let mut nested = ModulePaths {
+            lua: self.lua,
+            path: format!("{}.{key}", self.path),
+            parents: self
+                .parents
+                .iter()
+                .map(|v| *v)
+                .chain([type_name::<V>()])
+                .collect(),
+            nested: Vec::new(),
+        };
+
+        V::add_fields(&mut nested)?;
+        V::add_methods(&mut nested)?;
+
+        self.nested.push((self.path.clone(), key));
+        self.nested.extend(nested.nested);
+        Ok(())
+    }
+}
+
+impl<'a> ModuleMethods for ModulePaths<'a> {
+    fn add_function<K, F, A, R>(&mut self, _name: K, _function: F) -> mlua::Result<()>
+    where
+        K: IntoLua,
+        F: Fn(&mlua::Lua, A) -> mlua::Result<R> + MaybeSend + 'static,
+        A: FromLuaMulti,
+        R: IntoLuaMulti,
+    {
+        Ok(())
+    }
+
+    fn add_meta_function<K, F, A, R>(&mut self, _name: K, _function: F) -> mlua::Result<()>
+    where
+        K: IntoLua,
+        F: Fn(&mlua::Lua, A) -> mlua::Result<R> + MaybeSend + 'static,
+        A: FromLuaMulti,
+        R: IntoLuaMulti,
+    {
+        Ok(())
+    }
+
+    fn add_method<K, F, A, R>(&mut self, _name: K, _function: F) -> mlua::Result<()>
+    where
+        K: IntoLua,
+        F: Fn(&mlua::Lua, mlua::Table, A) -> mlua::Result<R> + MaybeSend + 'static,
+        A: FromLuaMulti,
+        R: IntoLuaMulti,
+    {
+        Ok(())
+    }
+
+    fn add_meta_method<K, F, A, R>(&mut self, _name: K, _function: F) -> mlua::Result<()>
+    where
+        K: IntoLua,
+        F: Fn(&mlua::Lua, mlua::Table, A) -> mlua::Result<R> + MaybeSend + 'static,
+        A: FromLuaMulti,
+        R: IntoLuaMulti,
+    {
+        Ok(())
+    }
+
+    #[cfg(feature = "async")]
+    fn add_async_function<K, F, A, R, FR>(&mut self, _name: K, _function: F) -> mlua::Result<()>
+    where
+        K: IntoLua,
+        F: Fn(mlua::Lua, A) -> FR + MaybeSend + 'static,
+        A: FromLuaMulti,
+        R: IntoLuaMulti,
+        FR: std::future::Future<Output = mlua::Result<R>> + MaybeSend + 'static,
+    {
+        Ok(())
+    }
+
+    #[cfg(feature = "async")]
+    fn add_async_method<K, F, A, R, FR>(&mut self, _name: K, _function: F) -> mlua::Result<()>
+    where
+        K: IntoLua,
+        F: Fn(mlua::Lua, mlua::Table, A) -> FR + MaybeSend + 'static,
+        A: FromLuaMulti,
+        R: IntoLuaMulti,
+        FR: std::future::Future<Output = mlua::Result<R>> + MaybeSend + 'static,
+    {
+        Ok(())
+    }
 }
@@ -0,0 +1,229 @@
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf, MAIN_SEPARATOR_STR},
+    str::FromStr,
+};
+
+/// Suffix of the versioned `LUA_PATH` and `LUA_CPATH` environment variables
+#[cfg(feature = "lua54")]
+const ENV_SUFFIX: &str = "_5_4";
+#[cfg(feature = "lua53")]
+const ENV_SUFFIX: &str = "_5_3";
+#[cfg(feature = "lua52")]
+const ENV_SUFFIX: &str = "_5_2";
+#[cfg(not(any(feature = "lua54", feature = "lua53", feature = "lua52")))]
+const ENV_SUFFIX: &str = "";
+
+/// Ordered list of templates used by `require` to search for modules. i.e. `package.path` and
+/// `package.cpath`
+///
+/// Each template is a path where `?` is replaced with the module name, with each `.` replaced by
+/// the directory separator. Templates are unique, adding a template that already exists moves it
+/// to the new position.
+///
+/// Use [`LuaExtras::update_path`][super::LuaExtras::update_path] or
+/// [`LuaExtras::update_cpath`][super::LuaExtras::update_cpath] to change the value in lua with a
+/// single write.
+///
+/// # Example
+///
+/// ```
+/// use mlua_extras::{mlua::Lua, extras::{LuaExtras, PackagePath}};
+///
+/// let lua = Lua::new();
+/// lua.update_path(|path| {
+///     path.prepend_dir("scripts");
+///     path.remove("./?.lua");
+///     Ok(())
+/// })?;
+///
+/// assert!(lua.package_path()?.contains("scripts/?/init.lua"));
+/// ```
+#[derive(Default, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PackagePath {
+    templates: Vec<String>,
+}
+
+impl PackagePath {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse a `;` separated list of templates
+    ///
+    /// Empty templates, including the `;;` default marker, are ignored. Use
+    /// [`parse_with_default`][PackagePath::parse_with_default] to replace the marker.
+    pub fn parse(value: &str) -> Self {
+        let mut path = Self::new();
+        path.extend(value.split(';'));
+        path
+    }
+
+    /// Parse a `;` separated list of templates where `;;` is replaced with the default templates
+    ///
+    /// This matches how lua reads the `LUA_PATH` and `LUA_CPATH` environment variables
+    pub fn parse_with_default(value: &str, default: &PackagePath) -> Self {
+        match value.split_once(";;") {
+            Some((before, after)) => {
+                let mut path = Self::parse(before);
+                path.extend(default.iter());
+                path.extend(after.split(';'));
+                path
+            }
+            None => Self::parse(value),
+        }
+    }
+
+    /// Read `LUA_PATH_5_4` or `LUA_PATH`, using the variable suffix for the enabled lua version
+    ///
+    /// Returns `None` if neither variable is set
+    pub fn from_env_path(default: &PackagePath) -> Option<Self> {
+        Self::from_env("LUA_PATH", default)
+    }
+
+    /// Read `LUA_CPATH_5_4` or `LUA_CPATH`, using the variable suffix for the enabled lua version
+    ///
+    /// Returns `None` if neither variable is set
+    pub fn from_env_cpath(default: &PackagePath) -> Option<Self> {
+        Self::from_env("LUA_CPATH", default)
+    }
+
+    fn from_env(name: &str, default: &PackagePath) -> Option<Self> {
+        let versioned = (!ENV_SUFFIX.is_empty())
+            .then(|| std::env::var(format!("{name}{ENV_SUFFIX}")).ok())
+            .flatten();
+        versioned
+            .or_else(|| std::env::var(name).ok())
+            .map(|value| Self::parse_with_default(&value, default))
+    }
+
+    /// Check if the template is in the path
+    pub fn contains(&self, template: impl AsRef<str>) -> bool {
+        let template = template.as_ref().trim();
+        self.templates.iter().any(|t| t == template)
+    }
+
+    /// Remove the template returning whether it was in the path
+    pub fn remove(&mut self, template: impl AsRef<str>) -> bool {
+        let template = template.as_ref().trim();
+        let len = self.templates.len();
+        self.templates.retain(|t| t != template);
+        self.templates.len() != len
+    }
+
+    /// Add a template before all other templates
+    pub fn prepend(&mut self, template: impl AsRef<Path>) {
+        self.insert(0, template);
+    }
+
+    /// Add a template after all other templates
+    pub fn append(&mut self, template: impl AsRef<Path>) {
+        self.insert(self.templates.len(), template);
+    }
+
+    /// Add a template at the index, moving it if it is already in the path
+    ///
+    /// The index is clamped to the number of templates
+    pub fn insert(&mut self, index: usize, template: impl AsRef<Path>) {
+        let template = template.as_ref().display().to_string();
+        let template = template.trim();
+        if template.is_empty() {
+            return;
+        }
+
+        let index = match self.templates.iter().position(|t| t == template) {
+            Some(existing) => {
+                self.templates.remove(existing);
+                if existing < index {
+                    index - 1
+                } else {
+                    index
+                }
+            }
+            None => index,
+        };
+        self.templates
+            .insert(index.min(self.templates.len()), template.to_string());
+    }
+
+    /// Add lua module templates for a directory before all other templates
+    ///
+    /// Adds both `<dir>/?.lua` and `<dir>/?/init.lua`
+    pub fn prepend_dir(&mut self, dir: impl AsRef<Path>) {
+        let [file, init] = Self::dir_templates(dir.as_ref());
+        self.prepend(init);
+        self.prepend(file);
+    }
+
+    /// Add lua module templates for a directory after all other templates
+    ///
+    /// Adds both `<dir>/?.lua` and `<dir>/?/init.lua`
+    pub fn append_dir(&mut self, dir: impl AsRef<Path>) {
+        let [file, init] = Self::dir_templates(dir.as_ref());
+        self.append(file);
+        self.append(init);
+    }
+
+    fn dir_templates(dir: &Path) -> [PathBuf; 2] {
+        [dir.join("?.lua"), dir.join("?").join("init.lua")]
+    }
+
+    /// Paths that are checked for the module, in order, the same as `package.searchpath`
+    pub fn candidates<'a>(&'a self, name: &str) -> impl Iterator<Item = PathBuf> + 'a {
+        let name = name.replace('.', MAIN_SEPARATOR_STR);
+        self.templates
+            .iter()
+            .map(move |template| PathBuf::from(template.replace('?', &name)))
+    }
+
+    /// Find the first file that exists for the module, the same as `package.searchpath`
+    pub fn search(&self, name: &str) -> Option<PathBuf> {
+        self.candidates(name).find(|path| path.is_file())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.templates.iter().map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.templates.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.templates.is_empty()
+    }
+}
+
+impl<S: AsRef<Path>> Extend<S> for PackagePath {
+    fn extend<T: IntoIterator<Item = S>>(&mut self, iter: T) {
+        for template in iter {
+            let template = template.as_ref().display().to_string();
+            let template = template.trim();
+            if !template.is_empty() && !self.contains(template) {
+                self.templates.push(template.to_string());
+            }
+        }
+    }
+}
+
+impl<S: AsRef<Path>> FromIterator<S> for PackagePath {
+    fn from_iter<T: IntoIterator<Item = S>>(iter: T) -> Self {
+        let mut path = Self::new();
+        path.extend(iter);
+        path
+    }
+}
+
+impl FromStr for PackagePath {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self::parse(s))
+    }
+}
+
+impl Display for PackagePath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.templates.join(";"))
+    }
+}
@@ -0,0 +1,234 @@
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeMap},
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+use mlua::{Function, IntoLuaMulti, Lua, MultiValue, Table, Value};
+
+use super::{insert_searcher, LuaExtras};
+
+/// A lua file that was loaded with `require`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrackedModule {
+    /// File the module was loaded from
+    pub path: PathBuf,
+    modified: Option<SystemTime>,
+    hash: u64,
+    missing: bool,
+}
+
+impl TrackedModule {
+    fn read(path: &Path) -> mlua::Result<(Self, Vec<u8>)> {
+        let source = std::fs::read(path).map_err(mlua::Error::external)?;
+        let modified = std::fs::metadata(path)
+            .and_then(|meta| meta.modified())
+            .ok();
+        Ok((
+            Self {
+                path: path.to_path_buf(),
+                modified,
+                hash: hash(&source),
+                missing: false,
+            },
+            source,
+        ))
+    }
+}
+
+fn hash(source: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    source.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Modules that were reloaded and the modules that failed to reload
+#[derive(Default, Debug)]
+pub struct ReloadReport {
+    pub reloaded: Vec<String>,
+    pub failed: Vec<(String, mlua::Error)>,
+}
+
+impl ReloadReport {
+    /// Check if nothing was changed
+    pub fn is_empty(&self) -> bool {
+        self.reloaded.is_empty() && self.failed.is_empty()
+    }
+}
+
+/// Reloads lua modules when the files they were required from change
+///
+/// [`install`][HotReloader::install] adds a searcher that loads modules from `package.path`, the
+/// same as lua's own searcher, while recording the file each module came from. Calling
+/// [`reload`][HotReloader::reload] checks each file's modified time and contents, and modules
+/// that changed are removed from `package.loaded` and required again.
+///
+/// If the new module is a table with a `__reload` function it is called with the new and old
+/// module so state can be carried over. Modules that fail to reload, including when their
+/// `__reload` function fails, keep their previous value. Modules that hold a reference to a
+/// reloaded module keep the old value until they are reloaded themselves.
+///
+/// # Example
+///
+/// ```no_run
+/// use mlua_extras::{mlua::Lua, extras::HotReloader};
+///
+/// let lua = Lua::new();
+/// let reloader = HotReloader::install(&lua)?;
+///
+/// lua.load("config = require('config')").exec()?;
+///
+/// loop {
+///     let report = reloader.reload(&lua);
+///     for (module, err) in report.failed {
+///         eprintln!("failed to reload {module}: {err}");
+///     }
+///     # break;
+/// }
+/// ```
+#[derive(Default, Debug, Clone)]
+pub struct HotReloader {
+    modules: Arc<Mutex<BTreeMap<String, TrackedModule>>>,
+}
+
+impl HotReloader {
+    /// Add the tracking searcher after the `package.preload` searcher
+    pub fn install(lua: &Lua) -> mlua::Result<Self> {
+        let reloader = Self::default();
+        let modules = reloader.modules.clone();
+
+        let searcher = lua.create_function(move |lua, name: String| -> mlua::Result<MultiValue> {
+            // Not finding a module is left to lua's own searcher which reports the paths it tried
+            let Some(path) = lua.package_path()?.search(&name) else {
+                return Value::Nil.into_lua_multi(lua);
+            };
+
+            let (module, source) = TrackedModule::read(&path)?;
+            let chunk_name = path.display().to_string();
+            let loader = lua
+                .load(source)
+                .set_name(format!("@{chunk_name}"))
+                .into_function()
+                .map_err(|err| {
+                    mlua::Error::runtime(format!(
+                        "error loading module '{name}' from file '{chunk_name}':\n\t{err}"
+                    ))
+                })?;
+
+            modules
+                .lock()
+                .unwrap_or_else(|err| err.into_inner())
+                .insert(name, module);
+            (loader, chunk_name).into_lua_multi(lua)
+        })?;
+
+        insert_searcher(lua, searcher)?;
+        Ok(reloader)
+    }
+
+    /// Names and files of the modules that have been required
+    pub fn tracked(&self) -> Vec<(String, PathBuf)> {
+        self.modules
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .iter()
+            .map(|(name, module)| (name.clone(), module.path.clone()))
+            .collect()
+    }
+
+    /// Stop tracking a module so it is no longer reloaded
+    pub fn untrack(&self, name: impl AsRef<str>) -> bool {
+        self.modules
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .remove(name.as_ref())
+            .is_some()
+    }
+
+    /// Reload every module whose file changed since it was required
+    ///
+    /// Errors are collected in the report instead of stopping the remaining reloads
+    pub fn reload(&self, lua: &Lua) -> ReloadReport {
+        let mut report = ReloadReport::default();
+
+        let changed = self
+            .modules
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .iter_mut()
+            .filter_map(|(name, module)| {
+                let modified = match std::fs::metadata(&module.path) {
+                    Ok(meta) => meta.modified().ok(),
+                    // A deleted file is only reported once instead of on every poll
+                    Err(_) if module.missing => return None,
+                    Err(_) => {
+                        module.missing = true;
+                        return Some(name.clone());
+                    }
+                };
+                if !module.missing && modified.is_some() && modified == module.modified {
+                    return None;
+                }
+
+                // The file is marked as seen so a failed reload is only reported once per change
+                module.missing = false;
+                module.modified = modified;
+                match std::fs::read(&module.path) {
+                    // Only the modified time changed, i.e. the file was saved without any edits
+                    Ok(source) if hash(&source) == module.hash => None,
+                    Ok(source) => {
+                        module.hash = hash(&source);
+                        Some(name.clone())
+                    }
+                    Err(_) => Some(name.clone()),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        for name in changed {
+            match self.reload_module(lua, &name) {
+                Ok(()) => report.reloaded.push(name),
+                Err(err) => report.failed.push((name, err)),
+            }
+        }
+        report
+    }
+
+    /// Remove the module from `package.loaded` and require it again
+    ///
+    /// The previous value is restored if requiring the module or calling it's `__reload` function
+    /// fails
+    pub fn reload_module(&self, lua: &Lua, name: impl AsRef<str>) -> mlua::Result<()> {
+        let name = name.as_ref();
+        let loaded = lua
+            .globals()
+            .get::<Table>("package")?
+            .get::<Table>("loaded")?;
+
+        let old = loaded.get::<Value>(name)?;
+        loaded.set(name, Value::Nil)?;
+
+        let new = match lua.globals().get::<Function>("require")?.call::<Value>(name) {
+            Ok(new) => new,
+            Err(err) => {
+                loaded.set(name, old)?;
+                return Err(err);
+            }
+        };
+
+        if let Value::Table(module) = &new {
+            let reloaded = match module.get::<Option<Function>>("__reload") {
+                Ok(Some(hook)) => hook.call::<()>((new.clone(), old.clone())),
+                Ok(None) => Ok(()),
+                Err(err) => Err(err),
+            };
+            if let Err(err) = reloaded {
+                loaded.set(name, old)?;
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+}
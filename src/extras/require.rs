@@ -1,4 +1,4 @@
-use mlua::{Error, FromLua, Table};
+use mlua::{Error, ErrorContext, FromLua, IntoLua, Lua, ObjectLike, Table, Value};
 
 /// Adds a similar syntax to tables as lua's `require` function.
 ///
@@ -20,32 +20,320 @@ use mlua::{Error, FromLua, Table};
 /// ```lua
 /// local unpack = require("table").unpack
 /// ```
+///
+/// Paths may also index with brackets, either with an integer or a quoted key, and go through
+/// userdata and tables with an `__index` metamethod
+///
+/// ```
+/// use mlua_extras::{mlua::Lua, extras::LuaExtras};
+///
+/// let lua = Lua::new();
+/// lua.load(r#"
+///     items = { "first", { name = "second" } }
+///     map = { ["key with dots.and spaces"] = 3 }
+/// "#).exec()?;
+///
+/// assert_eq!(lua.require::<String>("items[2].name")?, "second");
+/// assert_eq!(lua.require::<i64>(r#"map["key with dots.and spaces"]"#)?, 3);
+///
+/// // cannot index 'items[1]' with 'name': expected a table or userdata but found string
+/// assert!(lua.require::<String>("items[1].name").is_err());
+/// ```
 pub trait Require {
     /// Fetch a nested lua value from the table
+    ///
+    /// Errors name the segment of the path that could not be indexed and the type that was found
     fn require<R: FromLua>(&self, path: impl AsRef<str>) -> mlua::Result<R>;
+
+    /// Fetch a nested table creating any missing tables along the path
+    ///
+    /// Returns an error if a value along the path is not a table
+    fn ensure_table(&self, lua: &Lua, path: impl AsRef<str>) -> mlua::Result<Table>;
+
+    /// Set a nested value creating any missing tables along the path
+    fn set_nested<V: IntoLua>(
+        &self,
+        lua: &Lua,
+        path: impl AsRef<str>,
+        value: V,
+    ) -> mlua::Result<()>;
+
+    /// Remove a nested value returning whether it existed
+    fn remove_nested(&self, path: impl AsRef<str>) -> mlua::Result<bool>;
+
+    /// Check if a nested value exists and is not `nil`
+    fn exists(&self, path: impl AsRef<str>) -> mlua::Result<bool>;
 }
 
-impl Require for Table {
-    fn require<R: FromLua>(&self, path: impl AsRef<str>) -> mlua::Result<R> {
-        let segments = path
-            .as_ref()
-            .split('.')
-            .filter_map(|v| (!v.trim().is_empty()).then_some(v.trim()))
-            .collect::<Vec<_>>();
-
-        let mut module = self.clone();
-        if !segments.is_empty() {
-            for seg in &segments[..segments.len() - 1] {
-                module = module.get::<Table>(*seg)?;
+/// A single key in a path. i.e. `name`, `[3]`, or `["key with dots"]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Name(String),
+    Index(i64),
+}
+
+impl IntoLua for Segment {
+    fn into_lua(self, lua: &Lua) -> mlua::Result<Value> {
+        match self {
+            Self::Name(name) => name.into_lua(lua),
+            Self::Index(index) => Ok(Value::Integer(index as _)),
+        }
+    }
+}
+
+/// Write the segments as a path. i.e. `items[3].name`
+fn display(segments: &[Segment]) -> String {
+    let mut path = String::new();
+    for segment in segments {
+        match segment {
+            Segment::Name(name)
+                if !name.is_empty()
+                    && !name.contains(['.', '[', ']', '"'])
+                    && name.trim() == name =>
+            {
+                if !path.is_empty() {
+                    path.push('.');
+                }
+                path.push_str(name);
+            }
+            Segment::Name(name) => path.push_str(&format!("[{name:?}]")),
+            Segment::Index(index) => path.push_str(&format!("[{index}]")),
+        }
+    }
+    path
+}
+
+/// Parse a path of dotted names and bracket indexes. i.e. `items[3].name` or `map["key with dots"]`
+///
+/// Whitespace around names is ignored along with empty names, i.e. `a..b` is the same as `a.b`
+fn segments(path: &str) -> mlua::Result<Vec<Segment>> {
+    let error = |position: usize, reason: &str| {
+        Error::runtime(format!(
+            "invalid path {path:?}: {reason} at position {position}"
+        ))
+    };
+
+    let mut segments = Vec::new();
+    let mut chars = path.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        match c {
+            '.' => {}
+            ']' => return Err(error(start, "unexpected `]`")),
+            '[' => {
+                while chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+
+                match chars.peek().copied() {
+                    Some((_, quote @ ('"' | '\''))) => {
+                        chars.next();
+                        let mut key = String::new();
+                        loop {
+                            match chars.next() {
+                                Some((_, '\\')) => match chars.next() {
+                                    Some((_, c)) => key.push(c),
+                                    None => return Err(error(path.len(), "unterminated string")),
+                                },
+                                Some((_, c)) if c == quote => break,
+                                Some((_, c)) => key.push(c),
+                                None => return Err(error(path.len(), "unterminated string")),
+                            }
+                        }
+                        while chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+                        match chars.next() {
+                            Some((_, ']')) => segments.push(Segment::Name(key)),
+                            Some((i, _)) => return Err(error(i, "expected `]`")),
+                            None => return Err(error(path.len(), "expected `]`")),
+                        }
+                    }
+                    _ => {
+                        let mut index = String::new();
+                        loop {
+                            match chars.next() {
+                                Some((_, ']')) => break,
+                                Some((_, c)) => index.push(c),
+                                None => return Err(error(path.len(), "expected `]`")),
+                            }
+                        }
+                        match index.trim().parse::<i64>() {
+                            Ok(index) => segments.push(Segment::Index(index)),
+                            Err(_) => {
+                                return Err(error(
+                                    start,
+                                    "expected an integer or a quoted string inside of `[]`",
+                                ))
+                            }
+                        }
+                    }
+                }
+            }
+            c => {
+                let mut name = String::from(c);
+                while let Some((_, c)) = chars.next_if(|(_, c)| !matches!(c, '.' | '[' | ']')) {
+                    name.push(c);
+                }
+                let name = name.trim();
+                if !name.is_empty() {
+                    segments.push(Segment::Name(name.to_string()));
+                }
             }
         }
+    }
+    Ok(segments)
+}
+
+/// Index a table or userdata, including through `__index` metamethods
+fn index(value: Value, segments: &[Segment], i: usize) -> mlua::Result<Value> {
+    let key = segments[i].clone();
+    let result = match value {
+        Value::Table(table) => table.get::<Value>(key),
+        Value::UserData(userdata) => userdata.get::<Value>(key),
+        other => return Err(not_indexable(&other, segments, i)),
+    };
+    result.with_context(|_| {
+        format!(
+            "failed to index '{}' with '{}'",
+            display(&segments[..i]),
+            display(&segments[i..=i]),
+        )
+    })
+}
+
+fn not_indexable(value: &Value, segments: &[Segment], i: usize) -> Error {
+    Error::runtime(format!(
+        "cannot index '{}' with '{}': expected a table or userdata but found {}",
+        display(&segments[..i]),
+        display(&segments[i..=i]),
+        value.type_name()
+    ))
+}
+
+/// Fetch the nested table creating any missing tables along the path
+fn ensure_segments(table: &Table, lua: &Lua, segments: &[Segment]) -> mlua::Result<Table> {
+    let mut table = table.clone();
+    for i in 0..segments.len() {
+        table = match index(Value::Table(table.clone()), segments, i)? {
+            Value::Table(next) => next,
+            Value::Nil => {
+                let next = lua.create_table()?;
+                table.set(segments[i].clone(), next.clone())?;
+                next
+            }
+            other => {
+                return Err(Error::runtime(format!(
+                    "expected a table at '{}' but found {}",
+                    display(&segments[..=i]),
+                    other.type_name()
+                )))
+            }
+        };
+    }
+    Ok(table)
+}
 
-        match segments.last() {
-            Some(seg) => module.get::<R>(*seg),
-            None => Err(Error::runtime(format!(
+/// Walk all but the last segment returning `None` if a value along the way is missing or can't be
+/// indexed
+fn walk(table: &Table, segments: &[Segment]) -> mlua::Result<Option<Value>> {
+    let mut value = Value::Table(table.clone());
+    for i in 0..segments.len().saturating_sub(1) {
+        value = match value {
+            Value::Table(_) | Value::UserData(_) => index(value, segments, i)?,
+            _ => return Ok(None),
+        };
+    }
+    Ok(Some(value))
+}
+
+impl Require for Table {
+    fn require<R: FromLua>(&self, path: impl AsRef<str>) -> mlua::Result<R> {
+        let segments = segments(path.as_ref())?;
+        let Some(last) = segments.last() else {
+            return Err(Error::runtime(format!(
                 "module not found: {:?}",
                 path.as_ref()
-            ))),
+            )));
+        };
+
+        let mut value = Value::Table(self.clone());
+        for i in 0..segments.len() - 1 {
+            value = index(value, &segments, i)?;
+        }
+
+        let result = match value {
+            Value::Table(table) => table.get::<R>(last.clone()),
+            Value::UserData(userdata) => userdata.get::<R>(last.clone()),
+            other => return Err(not_indexable(&other, &segments, segments.len() - 1)),
+        };
+        result.with_context(|_| format!("failed to get '{}'", display(&segments)))
+    }
+
+    fn ensure_table(&self, lua: &Lua, path: impl AsRef<str>) -> mlua::Result<Table> {
+        ensure_segments(self, lua, &segments(path.as_ref())?)
+    }
+
+    fn set_nested<V: IntoLua>(
+        &self,
+        lua: &Lua,
+        path: impl AsRef<str>,
+        value: V,
+    ) -> mlua::Result<()> {
+        let segments = segments(path.as_ref())?;
+        match segments.split_last() {
+            Some((last, parents)) => ensure_segments(self, lua, parents)?.set(last.clone(), value),
+            None => Err(Error::runtime(format!("invalid path: {:?}", path.as_ref()))),
+        }
+    }
+
+    fn remove_nested(&self, path: impl AsRef<str>) -> mlua::Result<bool> {
+        let segments = segments(path.as_ref())?;
+        let Some(last) = segments.last() else {
+            return Ok(false);
+        };
+        let Some(Value::Table(parent)) = walk(self, &segments)? else {
+            return Ok(false);
+        };
+
+        if parent.get::<Value>(last.clone())?.is_nil() {
+            return Ok(false);
+        }
+        parent.set(last.clone(), Value::Nil)?;
+        Ok(true)
+    }
+
+    fn exists(&self, path: impl AsRef<str>) -> mlua::Result<bool> {
+        let segments = segments(path.as_ref())?;
+        if segments.is_empty() {
+            return Ok(false);
         }
+        match walk(self, &segments)? {
+            Some(parent @ (Value::Table(_) | Value::UserData(_))) => {
+                Ok(!index(parent, &segments, segments.len() - 1)?.is_nil())
+            }
+            _ => Ok(false),
+        }
+    }
+}
+
+/// Used by the [`function!`][crate::function] macro to create nested tables from either a [`Lua`]
+/// instance's globals or a [`Table`]
+#[doc(hidden)]
+pub trait NestedSource {
+    fn nested_table(&self, lua: &Lua, path: &str) -> mlua::Result<Table>;
+}
+
+impl NestedSource for Table {
+    fn nested_table(&self, lua: &Lua, path: &str) -> mlua::Result<Table> {
+        self.ensure_table(lua, path)
+    }
+}
+
+impl NestedSource for Lua {
+    fn nested_table(&self, _lua: &Lua, path: &str) -> mlua::Result<Table> {
+        self.globals().ensure_table(self, path)
+    }
+}
+
+/// Allows [`function!`][crate::function] to be used with a `&Lua` or `&Table`
+impl<T: NestedSource + ?Sized> NestedSource for &T {
+    fn nested_table(&self, lua: &Lua, path: &str) -> mlua::Result<Table> {
+        (**self).nested_table(lua, path)
     }
 }
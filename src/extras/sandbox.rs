@@ -0,0 +1,353 @@
+use mlua::{ChunkMode, FromLuaMulti, Function, IntoLua, Lua, Table, Value};
+
+use super::{EmbeddedModules, Module, SearchResult};
+use crate::MaybeSend;
+
+/// Groups of standard library functions that are safe to expose to untrusted scripts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Capability {
+    /// `assert`, `error`, `ipairs`, `next`, `pairs`, `pcall`, `rawequal`, `rawget`, `rawlen`,
+    /// `rawset`, `select`, `setmetatable`, `tonumber`, `tostring`, `type`, `unpack`, `xpcall`, and
+    /// `_VERSION`
+    ///
+    /// `getmetatable` is not included since it exposes the metatable shared by all strings
+    Base,
+    /// `print`
+    Print,
+    /// The `string` table without `string.dump`
+    ///
+    /// **IMPORTANT** Strings share a single metatable whose `__index` is the host's `string`
+    /// library, so string methods, including `("").dump`, are still reachable from any string in
+    /// the sandbox even without this capability.
+    String,
+    /// The `table` library
+    Table,
+    /// The `math` library
+    Math,
+    /// The `utf8` library
+    Utf8,
+    /// The `coroutine` library
+    Coroutine,
+    /// `os.clock`, `os.date`, `os.difftime`, and `os.time`
+    Time,
+}
+
+impl Capability {
+    /// Global paths that the capability allows
+    pub fn globals(&self) -> &'static [&'static str] {
+        match self {
+            Self::Base => &[
+                "assert",
+                "error",
+                "ipairs",
+                "next",
+                "pairs",
+                "pcall",
+                "rawequal",
+                "rawget",
+                "rawlen",
+                "rawset",
+                "select",
+                "setmetatable",
+                "tonumber",
+                "tostring",
+                "type",
+                "unpack",
+                "xpcall",
+                "_VERSION",
+            ],
+            Self::Print => &["print"],
+            Self::String => &[
+                "string.byte",
+                "string.char",
+                "string.find",
+                "string.format",
+                "string.gmatch",
+                "string.gsub",
+                "string.len",
+                "string.lower",
+                "string.match",
+                "string.pack",
+                "string.packsize",
+                "string.rep",
+                "string.reverse",
+                "string.sub",
+                "string.unpack",
+                "string.upper",
+            ],
+            Self::Table => &["table"],
+            Self::Math => &["math"],
+            Self::Utf8 => &["utf8"],
+            Self::Coroutine => &["coroutine"],
+            Self::Time => &["os.clock", "os.date", "os.difftime", "os.time"],
+        }
+    }
+
+    /// Every capability
+    pub fn all() -> [Capability; 8] {
+        [
+            Self::Base,
+            Self::Print,
+            Self::String,
+            Self::Table,
+            Self::Math,
+            Self::Utf8,
+            Self::Coroutine,
+            Self::Time,
+        ]
+    }
+}
+
+/// `require` for a sandbox that only uses the sandbox's searchers and caches modules in the
+/// sandbox instead of `package.loaded`
+///
+/// Written in lua so the sandbox environment can be garbage collected along with it
+const SANDBOX_REQUIRE: &str = r#"
+local env, searchers, loaded = ...
+local error, ipairs, type, concat, format = error, ipairs, type, table.concat, string.format
+
+return function(name)
+    if type(name) ~= "string" then
+        error(format("bad argument #1 to 'require' (string expected, got %s)", type(name)), 2)
+    end
+
+    local value = loaded[name]
+    if value ~= nil then
+        return value
+    end
+
+    local messages = {}
+    for _, searcher in ipairs(searchers) do
+        local loader, extra = searcher(name, env)
+        if type(loader) == "function" then
+            value = loader(name, extra)
+            if value == nil then
+                value = loaded[name]
+            end
+            if value == nil then
+                value = true
+            end
+            loaded[name] = value
+            return value, extra
+        elseif type(loader) == "string" then
+            if loader:sub(1, 1) ~= "\n" then
+                loader = "\n\t" .. loader
+            end
+            messages[#messages + 1] = loader
+        end
+    end
+    error(format("module '%s' not found:%s", name, concat(messages)), 2)
+end
+"#;
+
+/// Builds an isolated environment for running untrusted scripts
+///
+/// Nothing from lua's globals is available unless it is allowed. Allowed libraries are copied
+/// so scripts can't modify the tables used by other scripts or the host.
+///
+/// # Example
+///
+/// ```
+/// use mlua_extras::{mlua::Lua, extras::{Capability, LuaExtras, EmbeddedModules}};
+///
+/// let lua = Lua::new();
+/// let sandbox = lua
+///     .new_sandbox()?
+///     .capabilities([Capability::Base, Capability::String, Capability::Math])?
+///     .allow("os.time")?
+///     .embedded_modules(EmbeddedModules::new().with("util", "return { double = function(v) return v * 2 end }"))?
+///     .finish()?;
+///
+/// let value = sandbox.eval::<i64>("script", "return require('util').double(21)")?;
+/// assert_eq!(value, 42);
+/// assert!(sandbox.exec("script", "os.exit()").is_err());
+/// ```
+pub struct SandboxBuilder<'lua> {
+    lua: &'lua Lua,
+    env: Table,
+    searchers: Table,
+    loaded: Table,
+}
+
+impl<'lua> SandboxBuilder<'lua> {
+    /// Create an empty sandbox environment
+    pub fn new(lua: &'lua Lua) -> mlua::Result<Self> {
+        let env = lua.create_table()?;
+        env.set("_G", env.clone())?;
+        Ok(Self {
+            lua,
+            env,
+            searchers: lua.create_table()?,
+            loaded: lua.create_table()?,
+        })
+    }
+
+    /// Copy a global value into the sandbox. i.e. `print`, `string.format`, or `math`
+    ///
+    /// Tables are copied so changes made by scripts are not seen outside of the sandbox. Returns
+    /// an error if the global does not exist.
+    pub fn allow(self, path: impl AsRef<str>) -> mlua::Result<Self> {
+        let path = path.as_ref();
+        if !self.copy(path)? {
+            return Err(mlua::Error::runtime(format!(
+                "global `{path}` does not exist"
+            )));
+        }
+        Ok(self)
+    }
+
+    /// Allow the globals of a capability
+    ///
+    /// Globals that don't exist in the current lua version are skipped, i.e. `utf8` in lua 5.1
+    pub fn capability(self, capability: Capability) -> mlua::Result<Self> {
+        for path in capability.globals() {
+            self.copy(path)?;
+        }
+        Ok(self)
+    }
+
+    /// Allow the globals of multiple capabilities
+    pub fn capabilities(
+        mut self,
+        capabilities: impl IntoIterator<Item = Capability>,
+    ) -> mlua::Result<Self> {
+        for capability in capabilities {
+            self = self.capability(capability)?;
+        }
+        Ok(self)
+    }
+
+    /// Copy the value at the dotted path from lua's globals returning if it existed
+    fn copy(&self, path: &str) -> mlua::Result<bool> {
+        let segments = path
+            .split('.')
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .collect::<Vec<_>>();
+        let Some((last, parents)) = segments.split_last() else {
+            return Ok(false);
+        };
+
+        let mut source = self.lua.globals();
+        let mut target = self.env.clone();
+        for segment in parents {
+            source = match source.get::<Value>(*segment)? {
+                Value::Table(table) => table,
+                _ => return Ok(false),
+            };
+            target = match target.get::<Value>(*segment)? {
+                Value::Table(table) => table,
+                _ => {
+                    let table = self.lua.create_table()?;
+                    target.set(*segment, table.clone())?;
+                    table
+                }
+            };
+        }
+
+        match source.get::<Value>(*last)? {
+            Value::Nil => Ok(false),
+            Value::Table(table) => {
+                let copy = self.lua.create_table()?;
+                table.for_each::<Value, Value>(|key, value| copy.set(key, value))?;
+                target.set(*last, copy)?;
+                Ok(true)
+            }
+            value => {
+                target.set(*last, value)?;
+                Ok(true)
+            }
+        }
+    }
+
+    /// Set a global value in the sandbox
+    pub fn value<K: IntoLua, V: IntoLua>(self, name: K, value: V) -> mlua::Result<Self> {
+        self.env.set(name, value)?;
+        Ok(self)
+    }
+
+    /// Install a module as a global in the sandbox
+    pub fn module<M: Module>(self, name: impl AsRef<str>) -> mlua::Result<Self> {
+        self.env.set(name.as_ref(), M::module())?;
+        Ok(self)
+    }
+
+    /// Add a searcher used by the sandbox's `require`
+    ///
+    /// The searcher is passed the sandbox environment which should be used for any chunks it
+    /// loads. Adding a searcher makes `require` available in the sandbox, searchers are called
+    /// in the order they are added.
+    pub fn searcher<F>(self, searcher: F) -> mlua::Result<Self>
+    where
+        F: Fn(&Lua, Table, &str) -> mlua::Result<SearchResult> + MaybeSend + 'static,
+    {
+        let searcher = self
+            .lua
+            .create_function(move |lua, (name, env): (String, Table)| searcher(lua, env, &name))?;
+        self.searchers.raw_push(searcher)?;
+        Ok(self)
+    }
+
+    /// Allow `require` to load the embedded modules inside the sandbox environment
+    pub fn embedded_modules(self, modules: EmbeddedModules) -> mlua::Result<Self> {
+        self.searchers.raw_push(modules.searcher(self.lua)?)?;
+        Ok(self)
+    }
+
+    /// Finish building the sandbox
+    pub fn finish(self) -> mlua::Result<Sandbox<'lua>> {
+        if self.searchers.raw_len() > 0 {
+            let require = self
+                .lua
+                .load(SANDBOX_REQUIRE)
+                .set_name("=[sandbox require]")
+                .call::<Function>((self.env.clone(), self.searchers, self.loaded))?;
+            self.env.set("require", require)?;
+        }
+
+        Ok(Sandbox {
+            lua: self.lua,
+            env: self.env,
+        })
+    }
+}
+
+/// Runs chunks inside of an isolated environment built with [`SandboxBuilder`]
+pub struct Sandbox<'lua> {
+    lua: &'lua Lua,
+    env: Table,
+}
+
+impl Sandbox<'_> {
+    /// The sandbox's globals
+    pub fn env(&self) -> &Table {
+        &self.env
+    }
+
+    /// Load a chunk that runs inside of the sandbox
+    ///
+    /// The name is used in error messages and tracebacks. Only text chunks are accepted since
+    /// lua does not verify bytecode.
+    pub fn load(&self, name: impl AsRef<str>, source: impl AsRef<[u8]>) -> mlua::Result<Function> {
+        self.lua
+            .load(source.as_ref())
+            .set_name(format!("={}", name.as_ref()))
+            .set_mode(ChunkMode::Text)
+            .set_environment(self.env.clone())
+            .into_function()
+    }
+
+    /// Run a chunk inside of the sandbox
+    pub fn exec(&self, name: impl AsRef<str>, source: impl AsRef<[u8]>) -> mlua::Result<()> {
+        self.load(name, source)?.call(())
+    }
+
+    /// Run a chunk inside of the sandbox returning it's values
+    pub fn eval<R: FromLuaMulti>(
+        &self,
+        name: impl AsRef<str>,
+        source: impl AsRef<[u8]>,
+    ) -> mlua::Result<R> {
+        self.load(name, source)?.call(())
+    }
+}
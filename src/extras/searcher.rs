@@ -0,0 +1,55 @@
+use mlua::{Function, IntoLuaMulti, Lua, MultiValue, Value};
+
+use super::searcher_message;
+
+/// Result of a custom `package.searchers` function
+///
+/// # Example
+///
+/// ```
+/// use mlua_extras::{mlua::Lua, extras::{LuaExtras, SearchResult}};
+///
+/// let assets = std::collections::HashMap::from([("greet", "return 'hello'")]);
+///
+/// let lua = Lua::new();
+/// lua.append_searcher(move |lua, name| match assets.get(name) {
+///     Some(source) => Ok(SearchResult::loader(
+///         lua.load(*source).set_name(format!("@assets/{name}")).into_function()?,
+///     )),
+///     None => Ok(SearchResult::not_found(format!("no asset '{name}'"))),
+/// })?;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum SearchResult {
+    /// Function that is called with the module name and the extra value to load the module
+    Loader(Function, Value),
+    /// Reason the module was not found. Each searcher's message is combined into the error
+    /// raised by `require`
+    NotFound(String),
+}
+
+impl SearchResult {
+    /// Found the module with the loader
+    pub fn loader(loader: Function) -> Self {
+        Self::Loader(loader, Value::Nil)
+    }
+
+    /// Found the module with the loader which is also passed the extra value, i.e. a file path
+    pub fn loader_with(loader: Function, extra: Value) -> Self {
+        Self::Loader(loader, extra)
+    }
+
+    /// The module was not found with a message explaining where it was searched for
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::NotFound(message.into())
+    }
+}
+
+impl IntoLuaMulti for SearchResult {
+    fn into_lua_multi(self, lua: &Lua) -> mlua::Result<MultiValue> {
+        match self {
+            Self::Loader(loader, extra) => (loader, extra).into_lua_multi(lua),
+            Self::NotFound(message) => searcher_message(message).into_lua_multi(lua),
+        }
+    }
+}
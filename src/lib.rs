@@ -7,7 +7,7 @@ pub mod extras;
 pub use mlua;
 
 #[cfg(feature="derive")]
-pub use mlua_extras_derive::{Typed, UserData};
+pub use mlua_extras_derive::{lua_type, Typed, UserData};
 
 #[cfg(feature = "send")]
 /// Used by the `send` feature
@@ -345,4 +345,9 @@ pub trait TypedDataFields<T> {
     where
         F: 'static + MaybeSend + Fn(&Lua) -> mlua::Result<R>,
         R: IntoLua + Typed;
+
+    /// Document the keys that are handled by an `__index` meta method. i.e. `--- @field [string] integer`
+    ///
+    /// This only collects type information, the values are still provided by the meta method
+    fn add_index_field<K: Typed, V: Typed>(&mut self) {}
 }
@@ -6,7 +6,7 @@ use mlua::{AnyUserData, FromLua, FromLuaMulti, IntoLua, IntoLuaMulti, Lua, MetaM
 use mlua::{UserDataRef, UserDataRefMut};
 
 use crate::{
-    typed::{function::Return, generator::FunctionBuilder, Field, Func},
+    typed::{function::Return, generator::FunctionBuilder, Field, Func, Type},
     MaybeSend,
 };
 
@@ -28,6 +28,8 @@ pub struct TypedClassBuilder {
     pub meta_methods: BTreeMap<Cow<'static, str>, Func>,
     pub functions: BTreeMap<Cow<'static, str>, Func>,
     pub meta_functions: BTreeMap<Cow<'static, str>, Func>,
+    /// Index signatures keyed by the key type. i.e. `--- @field [string] integer`
+    pub index_fields: BTreeMap<Type, Field>,
 }
 
 impl TypedClassBuilder {
@@ -67,11 +69,12 @@ impl<T: TypedUserData> TypedDataFields<T> for TypedClassBuilder {
             .and_modify(|v| {
                 v.doc = self.queued_doc.take().map(|v| v.into());
                 v.ty = v.ty.clone() | V::ty();
+                v.optional = v.ty.is_optional();
             })
-            .or_insert(Field {
-                ty: V::ty(),
-                doc: self.queued_doc.take().map(|v| v.into()),
-            });
+            .or_insert(Field::new(
+                V::ty(),
+                self.queued_doc.take().map(|v| v.into()),
+            ));
     }
 
     fn add_field_function_set<S, A, F>(&mut self, name: &S, _: F)
@@ -86,11 +89,12 @@ impl<T: TypedUserData> TypedDataFields<T> for TypedClassBuilder {
             .and_modify(|v| {
                 v.doc = self.queued_doc.take().map(|v| v.into());
                 v.ty = v.ty.clone() | A::ty();
+                v.optional = v.ty.is_optional();
             })
-            .or_insert(Field {
-                ty: A::ty(),
-                doc: self.queued_doc.take().map(|v| v.into()),
-            });
+            .or_insert(Field::new(
+                A::ty(),
+                self.queued_doc.take().map(|v| v.into()),
+            ));
     }
 
     fn add_field_function_get<S, R, F>(&mut self, name: &S, _: F)
@@ -105,11 +109,12 @@ impl<T: TypedUserData> TypedDataFields<T> for TypedClassBuilder {
             .and_modify(|v| {
                 v.doc = self.queued_doc.take().map(|v| v.into());
                 v.ty = v.ty.clone() | R::ty();
+                v.optional = v.ty.is_optional();
             })
-            .or_insert(Field {
-                ty: R::ty(),
-                doc: self.queued_doc.take().map(|v| v.into()),
-            });
+            .or_insert(Field::new(
+                R::ty(),
+                self.queued_doc.take().map(|v| v.into()),
+            ));
     }
 
     fn add_field_function_get_set<S, R, A, GET, SET>(&mut self, name: &S, _: GET, _: SET)
@@ -126,11 +131,12 @@ impl<T: TypedUserData> TypedDataFields<T> for TypedClassBuilder {
             .and_modify(|v| {
                 v.doc = self.queued_doc.take().map(|v| v.into());
                 v.ty = v.ty.clone() | A::ty() | R::ty();
+                v.optional = v.ty.is_optional();
             })
-            .or_insert(Field {
-                ty: A::ty() | R::ty(),
-                doc: self.queued_doc.take().map(|v| v.into()),
-            });
+            .or_insert(Field::new(
+                A::ty() | R::ty(),
+                self.queued_doc.take().map(|v| v.into()),
+            ));
     }
 
     fn add_field_method_set<S, A, M>(&mut self, name: &S, _: M)
@@ -145,11 +151,12 @@ impl<T: TypedUserData> TypedDataFields<T> for TypedClassBuilder {
             .and_modify(|v| {
                 v.doc = self.queued_doc.take().map(|v| v.into());
                 v.ty = v.ty.clone() | A::ty();
+                v.optional = v.ty.is_optional();
             })
-            .or_insert(Field {
-                ty: A::ty(),
-                doc: self.queued_doc.take().map(|v| v.into()),
-            });
+            .or_insert(Field::new(
+                A::ty(),
+                self.queued_doc.take().map(|v| v.into()),
+            ));
     }
 
     fn add_field_method_get<S, R, M>(&mut self, name: &S, _: M)
@@ -164,11 +171,12 @@ impl<T: TypedUserData> TypedDataFields<T> for TypedClassBuilder {
             .and_modify(|v| {
                 v.doc = self.queued_doc.take().map(|v| v.into());
                 v.ty = v.ty.clone() | R::ty();
+                v.optional = v.ty.is_optional();
             })
-            .or_insert(Field {
-                ty: R::ty(),
-                doc: self.queued_doc.take().map(|v| v.into()),
-            });
+            .or_insert(Field::new(
+                R::ty(),
+                self.queued_doc.take().map(|v| v.into()),
+            ));
     }
 
     fn add_field_method_get_set<S, R, A, GET, SET>(&mut self, name: &S, _: GET, _: SET)
@@ -185,11 +193,12 @@ impl<T: TypedUserData> TypedDataFields<T> for TypedClassBuilder {
             .and_modify(|v| {
                 v.doc = self.queued_doc.take().map(|v| v.into());
                 v.ty = v.ty.clone() | A::ty() | R::ty();
+                v.optional = v.ty.is_optional();
             })
-            .or_insert(Field {
-                ty: A::ty() | R::ty(),
-                doc: self.queued_doc.take().map(|v| v.into()),
-            });
+            .or_insert(Field::new(
+                A::ty() | R::ty(),
+                self.queued_doc.take().map(|v| v.into()),
+            ));
     }
 
     fn add_meta_field<R, F>(&mut self, meta: MetaMethod, _: F)
@@ -203,11 +212,19 @@ impl<T: TypedUserData> TypedDataFields<T> for TypedClassBuilder {
             .and_modify(|v| {
                 v.doc = self.queued_doc.take().map(|v| v.into());
                 v.ty = v.ty.clone() | R::ty();
+                v.optional = v.ty.is_optional();
             })
-            .or_insert(Field {
-                ty: R::ty(),
-                doc: self.queued_doc.take().map(|v| v.into()),
-            });
+            .or_insert(Field::new(
+                R::ty(),
+                self.queued_doc.take().map(|v| v.into()),
+            ));
+    }
+
+    fn add_index_field<K: Typed, V: Typed>(&mut self) {
+        self.index_fields.insert(
+            K::ty(),
+            Field::new(V::ty(), self.queued_doc.take().map(|v| v.into())),
+        );
     }
 }
 
@@ -231,6 +248,7 @@ impl<T: TypedUserData> TypedDataMethods<T> for TypedClassBuilder {
                 params: A::get_types_as_params(),
                 returns: R::get_types_as_returns(),
                 doc: self.queued_doc.take().map(|v| v.into()),
+                is_async: false,
             },
         );
     }
@@ -253,6 +271,7 @@ impl<T: TypedUserData> TypedDataMethods<T> for TypedClassBuilder {
                 params: builder.params,
                 returns: builder.returns,
                 doc: self.queued_doc.take().map(|v| v.into()),
+                is_async: builder.is_async,
             },
         );
     }
@@ -271,6 +290,7 @@ impl<T: TypedUserData> TypedDataMethods<T> for TypedClassBuilder {
                 params: A::get_types_as_params(),
                 returns: R::get_types_as_returns(),
                 doc: self.queued_doc.take().map(|v| v.into()),
+                is_async: false,
             },
         );
     }
@@ -293,6 +313,7 @@ impl<T: TypedUserData> TypedDataMethods<T> for TypedClassBuilder {
                 params: builder.params,
                 returns: builder.returns,
                 doc: self.queued_doc.take().map(|v| v.into()),
+                is_async: builder.is_async,
             },
         );
     }
@@ -311,6 +332,7 @@ impl<T: TypedUserData> TypedDataMethods<T> for TypedClassBuilder {
                 params: A::get_types_as_params(),
                 returns: R::get_types_as_returns(),
                 doc: self.queued_doc.take().map(|v| v.into()),
+                is_async: false,
             },
         );
     }
@@ -333,6 +355,7 @@ impl<T: TypedUserData> TypedDataMethods<T> for TypedClassBuilder {
                 params: builder.params,
                 returns: builder.returns,
                 doc: self.queued_doc.take().map(|v| v.into()),
+                is_async: builder.is_async,
             },
         );
     }
@@ -350,6 +373,7 @@ impl<T: TypedUserData> TypedDataMethods<T> for TypedClassBuilder {
                 params: A::get_types_as_params(),
                 returns: R::get_types_as_returns(),
                 doc: self.queued_doc.take().map(|v| v.into()),
+                is_async: false,
             },
         );
     }
@@ -371,6 +395,7 @@ impl<T: TypedUserData> TypedDataMethods<T> for TypedClassBuilder {
                 params: builder.params,
                 returns: builder.returns,
                 doc: self.queued_doc.take().map(|v| v.into()),
+                is_async: builder.is_async,
             },
         );
     }
@@ -391,6 +416,7 @@ impl<T: TypedUserData> TypedDataMethods<T> for TypedClassBuilder {
                 params: A::get_types_as_params(),
                 returns: R::get_types_as_returns(),
                 doc: self.queued_doc.take().map(|v| v.into()),
+                is_async: true,
             },
         );
     }
@@ -419,6 +445,7 @@ impl<T: TypedUserData> TypedDataMethods<T> for TypedClassBuilder {
                 params: builder.params,
                 returns: builder.returns,
                 doc: self.queued_doc.take().map(|v| v.into()),
+                is_async: true,
             },
         );
     }
@@ -439,6 +466,7 @@ impl<T: TypedUserData> TypedDataMethods<T> for TypedClassBuilder {
                 params: A::get_types_as_params(),
                 returns: R::get_types_as_returns(),
                 doc: self.queued_doc.take().map(|v| v.into()),
+                is_async: true,
             },
         );
     }
@@ -467,6 +495,7 @@ impl<T: TypedUserData> TypedDataMethods<T> for TypedClassBuilder {
                 params: builder.params,
                 returns: builder.returns,
                 doc: self.queued_doc.take().map(|v| v.into()),
+                is_async: true,
             },
         );
     }
@@ -485,6 +514,7 @@ impl<T: TypedUserData> TypedDataMethods<T> for TypedClassBuilder {
                 params: A::get_types_as_params(),
                 returns: R::get_types_as_returns(),
                 doc: self.queued_doc.take().map(|v| v.into()),
+                is_async: false,
             },
         );
     }
@@ -507,6 +537,7 @@ impl<T: TypedUserData> TypedDataMethods<T> for TypedClassBuilder {
                 params: builder.params,
                 returns: builder.returns,
                 doc: self.queued_doc.take().map(|v| v.into()),
+                is_async: builder.is_async,
             },
         );
     }
@@ -524,6 +555,7 @@ impl<T: TypedUserData> TypedDataMethods<T> for TypedClassBuilder {
                 params: A::get_types_as_params(),
                 returns: R::get_types_as_returns(),
                 doc: self.queued_doc.take().map(|v| v.into()),
+                is_async: false,
             },
         );
     }
@@ -545,6 +577,7 @@ impl<T: TypedUserData> TypedDataMethods<T> for TypedClassBuilder {
                 params: builder.params,
                 returns: builder.returns,
                 doc: self.queued_doc.take().map(|v| v.into()),
+                is_async: builder.is_async,
             },
         );
     }
@@ -565,6 +598,7 @@ impl<T: TypedUserData> TypedDataMethods<T> for TypedClassBuilder {
                 params: A::get_types_as_params(),
                 returns: R::get_types_as_returns(),
                 doc: self.queued_doc.take().map(|v| v.into()),
+                is_async: true,
             },
         );
     }
@@ -593,6 +627,7 @@ impl<T: TypedUserData> TypedDataMethods<T> for TypedClassBuilder {
                 params: builder.params,
                 returns: builder.returns,
                 doc: self.queued_doc.take().map(|v| v.into()),
+                is_async: true,
             },
         );
     }
@@ -610,6 +645,7 @@ impl<T: TypedUserData> TypedDataMethods<T> for TypedClassBuilder {
                 params: A::get_types_as_params(),
                 returns: R::get_types_as_returns(),
                 doc: self.queued_doc.take().map(|v| v.into()),
+                is_async: false,
             },
         );
     }
@@ -631,6 +667,7 @@ impl<T: TypedUserData> TypedDataMethods<T> for TypedClassBuilder {
                 params: builder.params,
                 returns: builder.returns,
                 doc: self.queued_doc.take().map(|v| v.into()),
+                is_async: builder.is_async,
             },
         );
     }
@@ -651,6 +688,7 @@ impl<T: TypedUserData> TypedDataMethods<T> for TypedClassBuilder {
                     .map(|ty| Return { doc: None, ty })
                     .collect(),
                 doc: self.queued_doc.take().map(|v| v.into()),
+                is_async: false,
             },
         );
     }
@@ -676,6 +714,7 @@ impl<T: TypedUserData> TypedDataMethods<T> for TypedClassBuilder {
                 params: builder.params,
                 returns: builder.returns,
                 doc: self.queued_doc.take().map(|v| v.into()),
+                is_async: builder.is_async,
             },
         );
     }
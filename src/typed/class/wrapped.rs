@@ -1,12 +1,15 @@
 use mlua::{
-    AnyUserData, FromLua, FromLuaMulti, IntoLua, IntoLuaMulti, Lua, MetaMethod, UserData,
-    UserDataFields, UserDataMethods,
+    AnyUserData, FromLua, FromLuaMulti, IntoLua, IntoLuaMulti, Lua, MetaMethod, MultiValue,
+    UserData, UserDataFields, UserDataMethods,
 };
 
 #[cfg(feature = "async")]
 use mlua::{UserDataRef, UserDataRefMut};
 
-use crate::{typed::generator::FunctionBuilder, MaybeSend};
+use crate::{
+    typed::{function::Signature, generator::FunctionBuilder},
+    MaybeSend,
+};
 
 use super::{Typed, TypedDataFields, TypedDataMethods, TypedMultiValue};
 
@@ -113,10 +116,13 @@ impl<'ctx, T: UserData, U: UserDataMethods<T>> TypedDataMethods<T> for WrappedBu
         R: IntoLuaMulti + TypedMultiValue,
         M: 'static + MaybeSend + Fn(&Lua, &T, A) -> mlua::Result<R>,
     {
-        self.0.add_method(name.as_ref(), method)
+        let signature = Signature::method::<A, R>(name.as_ref());
+        self.0.add_method(name.as_ref(), move |lua, this, args: MultiValue| {
+            method(lua, this, signature.from_lua_args::<A>(args, lua)?)
+        })
     }
 
-    fn add_method_with<S, A, R, M, G>(&mut self, name: &S, method: M, _generator: G)
+    fn add_method_with<S, A, R, M, G>(&mut self, name: &S, method: M, generator: G)
     where
         S: ?Sized + AsRef<str>,
         A: FromLuaMulti + TypedMultiValue,
@@ -124,7 +130,10 @@ impl<'ctx, T: UserData, U: UserDataMethods<T>> TypedDataMethods<T> for WrappedBu
         M: 'static + MaybeSend + Fn(&Lua, &T, A) -> mlua::Result<R>,
         G: Fn(&mut FunctionBuilder<A, R>),
     {
-        self.0.add_method(name.as_ref(), method)
+        let signature = Signature::method::<A, R>(name.as_ref()).with(generator);
+        self.0.add_method(name.as_ref(), move |lua, this, args: MultiValue| {
+            method(lua, this, signature.from_lua_args::<A>(args, lua)?)
+        })
     }
 
     fn add_function<S, A, R, F>(&mut self, name: &S, function: F)
@@ -134,10 +143,13 @@ impl<'ctx, T: UserData, U: UserDataMethods<T>> TypedDataMethods<T> for WrappedBu
         R: IntoLuaMulti + TypedMultiValue,
         F: 'static + MaybeSend + Fn(&Lua, A) -> mlua::Result<R>,
     {
-        self.0.add_function(name.as_ref(), function)
+        let signature = Signature::function::<A, R>(name.as_ref());
+        self.0.add_function(name.as_ref(), move |lua, args: MultiValue| {
+            function(lua, signature.from_lua_args::<A>(args, lua)?)
+        })
     }
 
-    fn add_function_with<S, A, R, F, G>(&mut self, name: &S, function: F, _generator: G)
+    fn add_function_with<S, A, R, F, G>(&mut self, name: &S, function: F, generator: G)
     where
         S: ?Sized + AsRef<str>,
         A: FromLuaMulti + TypedMultiValue,
@@ -145,20 +157,26 @@ impl<'ctx, T: UserData, U: UserDataMethods<T>> TypedDataMethods<T> for WrappedBu
         F: 'static + MaybeSend + Fn(&Lua, A) -> mlua::Result<R>,
         G: Fn(&mut FunctionBuilder<A, R>),
     {
-        self.0.add_function(name.as_ref(), function)
+        let signature = Signature::function::<A, R>(name.as_ref()).with(generator);
+        self.0.add_function(name.as_ref(), move |lua, args: MultiValue| {
+            function(lua, signature.from_lua_args::<A>(args, lua)?)
+        })
     }
 
-    fn add_method_mut<S, A, R, M>(&mut self, name: &S, method: M)
+    fn add_method_mut<S, A, R, M>(&mut self, name: &S, mut method: M)
     where
         S: ?Sized + AsRef<str>,
         A: FromLuaMulti + TypedMultiValue,
         R: IntoLuaMulti + TypedMultiValue,
         M: 'static + MaybeSend + FnMut(&Lua, &mut T, A) -> mlua::Result<R>,
     {
-        self.0.add_method_mut(name.as_ref(), method)
+        let signature = Signature::method::<A, R>(name.as_ref());
+        self.0.add_method_mut(name.as_ref(), move |lua, this, args: MultiValue| {
+            method(lua, this, signature.from_lua_args::<A>(args, lua)?)
+        })
     }
 
-    fn add_method_mut_with<S, A, R, M, G>(&mut self, name: &S, method: M, _generator: G)
+    fn add_method_mut_with<S, A, R, M, G>(&mut self, name: &S, mut method: M, generator: G)
     where
         S: ?Sized + AsRef<str>,
         A: FromLuaMulti + TypedMultiValue,
@@ -166,7 +184,10 @@ impl<'ctx, T: UserData, U: UserDataMethods<T>> TypedDataMethods<T> for WrappedBu
         M: 'static + MaybeSend + FnMut(&Lua, &mut T, A) -> mlua::Result<R>,
         G: Fn(&mut FunctionBuilder<A, R>),
     {
-        self.0.add_method_mut(name.as_ref(), method)
+        let signature = Signature::method::<A, R>(name.as_ref()).with(generator);
+        self.0.add_method_mut(name.as_ref(), move |lua, this, args: MultiValue| {
+            method(lua, this, signature.from_lua_args::<A>(args, lua)?)
+        })
     }
 
     fn add_meta_method<A, R, M>(&mut self, meta: MetaMethod, method: M)
@@ -175,17 +196,23 @@ impl<'ctx, T: UserData, U: UserDataMethods<T>> TypedDataMethods<T> for WrappedBu
         R: IntoLuaMulti + TypedMultiValue,
         M: 'static + MaybeSend + Fn(&Lua, &T, A) -> mlua::Result<R>,
     {
-        self.0.add_meta_method(meta, method)
+        let signature = Signature::method::<A, R>(meta.as_ref());
+        self.0.add_meta_method(meta, move |lua, this, args: MultiValue| {
+            method(lua, this, signature.from_lua_args::<A>(args, lua)?)
+        })
     }
 
-    fn add_meta_method_with<A, R, M, G>(&mut self, meta: MetaMethod, method: M, _generator: G)
+    fn add_meta_method_with<A, R, M, G>(&mut self, meta: MetaMethod, method: M, generator: G)
     where
         A: FromLuaMulti + TypedMultiValue,
         R: IntoLuaMulti + TypedMultiValue,
         M: 'static + MaybeSend + Fn(&Lua, &T, A) -> mlua::Result<R>,
         G: Fn(&mut FunctionBuilder<A, R>),
     {
-        self.0.add_meta_method(meta, method)
+        let signature = Signature::method::<A, R>(meta.as_ref()).with(generator);
+        self.0.add_meta_method(meta, move |lua, this, args: MultiValue| {
+            method(lua, this, signature.from_lua_args::<A>(args, lua)?)
+        })
     }
 
     #[cfg(feature = "async")]
@@ -195,9 +222,20 @@ impl<'ctx, T: UserData, U: UserDataMethods<T>> TypedDataMethods<T> for WrappedBu
         M: Fn(Lua, UserDataRef<T>, A) -> MR + MaybeSend + 'static,
         A: FromLuaMulti + TypedMultiValue,
         MR: std::future::Future<Output = mlua::Result<R>> + 'static,
-        R: IntoLuaMulti,
+        R: IntoLuaMulti + TypedMultiValue,
     {
-        self.0.add_async_method(name.as_ref(), method)
+        let signature = Signature::method::<A, R>(name.as_ref());
+        self.0.add_async_method(name.as_ref(), move |lua, this, args: MultiValue| {
+            let fut = signature
+                .from_lua_args::<A>(args, &lua)
+                .map(|args| method(lua, this, args));
+            async move {
+                match fut {
+                    Ok(fut) => fut.await,
+                    Err(err) => Err(err),
+                }
+            }
+        })
     }
 
     #[cfg(feature = "async")]
@@ -205,7 +243,7 @@ impl<'ctx, T: UserData, U: UserDataMethods<T>> TypedDataMethods<T> for WrappedBu
         &mut self,
         name: &S,
         method: M,
-        _generator: G,
+        generator: G,
     ) where
         T: 'static,
         M: Fn(Lua, UserDataRef<T>, A) -> MR + MaybeSend + 'static,
@@ -214,7 +252,18 @@ impl<'ctx, T: UserData, U: UserDataMethods<T>> TypedDataMethods<T> for WrappedBu
         R: IntoLuaMulti + TypedMultiValue,
         G: Fn(&mut FunctionBuilder<A, R>),
     {
-        self.0.add_async_method(name.as_ref(), method)
+        let signature = Signature::method::<A, R>(name.as_ref()).with(generator);
+        self.0.add_async_method(name.as_ref(), move |lua, this, args: MultiValue| {
+            let fut = signature
+                .from_lua_args::<A>(args, &lua)
+                .map(|args| method(lua, this, args));
+            async move {
+                match fut {
+                    Ok(fut) => fut.await,
+                    Err(err) => Err(err),
+                }
+            }
+        })
     }
 
     #[cfg(feature = "async")]
@@ -224,9 +273,20 @@ impl<'ctx, T: UserData, U: UserDataMethods<T>> TypedDataMethods<T> for WrappedBu
         M: Fn(Lua, UserDataRefMut<T>, A) -> MR + MaybeSend + 'static,
         A: FromLuaMulti + TypedMultiValue,
         MR: std::future::Future<Output = mlua::Result<R>> + 'static,
-        R: IntoLuaMulti,
+        R: IntoLuaMulti + TypedMultiValue,
     {
-        self.0.add_async_method_mut(name.as_ref(), method)
+        let signature = Signature::method::<A, R>(name.as_ref());
+        self.0.add_async_method_mut(name.as_ref(), move |lua, this, args: MultiValue| {
+            let fut = signature
+                .from_lua_args::<A>(args, &lua)
+                .map(|args| method(lua, this, args));
+            async move {
+                match fut {
+                    Ok(fut) => fut.await,
+                    Err(err) => Err(err),
+                }
+            }
+        })
     }
 
     #[cfg(feature = "async")]
@@ -234,7 +294,7 @@ impl<'ctx, T: UserData, U: UserDataMethods<T>> TypedDataMethods<T> for WrappedBu
         &mut self,
         name: &S,
         method: M,
-        _generator: G,
+        generator: G,
     ) where
         T: 'static,
         M: Fn(Lua, UserDataRefMut<T>, A) -> MR + MaybeSend + 'static,
@@ -243,20 +303,34 @@ impl<'ctx, T: UserData, U: UserDataMethods<T>> TypedDataMethods<T> for WrappedBu
         R: IntoLuaMulti + TypedMultiValue,
         G: Fn(&mut FunctionBuilder<A, R>),
     {
-        self.0.add_async_method_mut(name.as_ref(), method)
-    }
-
-    fn add_function_mut<S, A, R, F>(&mut self, name: &S, function: F)
+        let signature = Signature::method::<A, R>(name.as_ref()).with(generator);
+        self.0.add_async_method_mut(name.as_ref(), move |lua, this, args: MultiValue| {
+            let fut = signature
+                .from_lua_args::<A>(args, &lua)
+                .map(|args| method(lua, this, args));
+            async move {
+                match fut {
+                    Ok(fut) => fut.await,
+                    Err(err) => Err(err),
+                }
+            }
+        })
+    }
+
+    fn add_function_mut<S, A, R, F>(&mut self, name: &S, mut function: F)
     where
         S: ?Sized + AsRef<str>,
         A: FromLuaMulti + TypedMultiValue,
         R: IntoLuaMulti + TypedMultiValue,
         F: 'static + MaybeSend + FnMut(&Lua, A) -> mlua::Result<R>,
     {
-        self.0.add_function_mut(name.as_ref(), function)
+        let signature = Signature::function::<A, R>(name.as_ref());
+        self.0.add_function_mut(name.as_ref(), move |lua, args: MultiValue| {
+            function(lua, signature.from_lua_args::<A>(args, lua)?)
+        })
     }
 
-    fn add_function_mut_with<S, A, R, F, G>(&mut self, name: &S, function: F, _generator: G)
+    fn add_function_mut_with<S, A, R, F, G>(&mut self, name: &S, mut function: F, generator: G)
     where
         S: ?Sized + AsRef<str>,
         A: FromLuaMulti + TypedMultiValue,
@@ -264,7 +338,10 @@ impl<'ctx, T: UserData, U: UserDataMethods<T>> TypedDataMethods<T> for WrappedBu
         F: 'static + MaybeSend + FnMut(&Lua, A) -> mlua::Result<R>,
         G: Fn(&mut FunctionBuilder<A, R>),
     {
-        self.0.add_function_mut(name.as_ref(), function)
+        let signature = Signature::function::<A, R>(name.as_ref()).with(generator);
+        self.0.add_function_mut(name.as_ref(), move |lua, args: MultiValue| {
+            function(lua, signature.from_lua_args::<A>(args, lua)?)
+        })
     }
 
     fn add_meta_function<A, R, F>(&mut self, meta: MetaMethod, function: F)
@@ -273,17 +350,23 @@ impl<'ctx, T: UserData, U: UserDataMethods<T>> TypedDataMethods<T> for WrappedBu
         R: IntoLuaMulti + TypedMultiValue,
         F: 'static + MaybeSend + Fn(&Lua, A) -> mlua::Result<R>,
     {
-        self.0.add_meta_function(meta, function)
+        let signature = Signature::function::<A, R>(meta.as_ref());
+        self.0.add_meta_function(meta, move |lua, args: MultiValue| {
+            function(lua, signature.from_lua_args::<A>(args, lua)?)
+        })
     }
 
-    fn add_meta_function_with<A, R, F, G>(&mut self, meta: MetaMethod, function: F, _generator: G)
+    fn add_meta_function_with<A, R, F, G>(&mut self, meta: MetaMethod, function: F, generator: G)
     where
         A: FromLuaMulti + TypedMultiValue,
         R: IntoLuaMulti + TypedMultiValue,
         F: 'static + MaybeSend + Fn(&Lua, A) -> mlua::Result<R>,
         G: Fn(&mut FunctionBuilder<A, R>),
     {
-        self.0.add_meta_function(meta, function)
+        let signature = Signature::function::<A, R>(meta.as_ref()).with(generator);
+        self.0.add_meta_function(meta, move |lua, args: MultiValue| {
+            function(lua, signature.from_lua_args::<A>(args, lua)?)
+        })
     }
 
     #[cfg(feature = "async")]
@@ -295,7 +378,18 @@ impl<'ctx, T: UserData, U: UserDataMethods<T>> TypedDataMethods<T> for WrappedBu
         F: 'static + MaybeSend + Fn(Lua, A) -> FR,
         FR: std::future::Future<Output = mlua::Result<R>> + 'static,
     {
-        self.0.add_async_function(name.as_ref(), function)
+        let signature = Signature::function::<A, R>(name.as_ref());
+        self.0.add_async_function(name.as_ref(), move |lua, args: MultiValue| {
+            let fut = signature
+                .from_lua_args::<A>(args, &lua)
+                .map(|args| function(lua, args));
+            async move {
+                match fut {
+                    Ok(fut) => fut.await,
+                    Err(err) => Err(err),
+                }
+            }
+        })
     }
 
     #[cfg(feature = "async")]
@@ -303,7 +397,7 @@ impl<'ctx, T: UserData, U: UserDataMethods<T>> TypedDataMethods<T> for WrappedBu
         &mut self,
         name: &S,
         function: F,
-        _generator: G,
+        generator: G,
     ) where
         S: AsRef<str>,
         A: FromLuaMulti + TypedMultiValue,
@@ -312,48 +406,71 @@ impl<'ctx, T: UserData, U: UserDataMethods<T>> TypedDataMethods<T> for WrappedBu
         FR: std::future::Future<Output = mlua::Result<R>> + 'static,
         G: Fn(&mut FunctionBuilder<A, R>),
     {
-        self.0.add_async_function(name.as_ref(), function)
-    }
-
-    fn add_meta_method_mut<A, R, M>(&mut self, meta: MetaMethod, method: M)
+        let signature = Signature::function::<A, R>(name.as_ref()).with(generator);
+        self.0.add_async_function(name.as_ref(), move |lua, args: MultiValue| {
+            let fut = signature
+                .from_lua_args::<A>(args, &lua)
+                .map(|args| function(lua, args));
+            async move {
+                match fut {
+                    Ok(fut) => fut.await,
+                    Err(err) => Err(err),
+                }
+            }
+        })
+    }
+
+    fn add_meta_method_mut<A, R, M>(&mut self, meta: MetaMethod, mut method: M)
     where
         A: FromLuaMulti + TypedMultiValue,
         R: IntoLuaMulti + TypedMultiValue,
         M: 'static + MaybeSend + FnMut(&Lua, &mut T, A) -> mlua::Result<R>,
     {
-        self.0.add_meta_method_mut(meta, method)
+        let signature = Signature::method::<A, R>(meta.as_ref());
+        self.0.add_meta_method_mut(meta, move |lua, this, args: MultiValue| {
+            method(lua, this, signature.from_lua_args::<A>(args, lua)?)
+        })
     }
 
-    fn add_meta_method_mut_with<A, R, M, G>(&mut self, meta: MetaMethod, method: M, _generator: G)
+    fn add_meta_method_mut_with<A, R, M, G>(&mut self, meta: MetaMethod, mut method: M, generator: G)
     where
         A: FromLuaMulti + TypedMultiValue,
         R: IntoLuaMulti + TypedMultiValue,
         M: 'static + MaybeSend + FnMut(&Lua, &mut T, A) -> mlua::Result<R>,
         G: Fn(&mut FunctionBuilder<A, R>),
     {
-        self.0.add_meta_method_mut(meta, method)
+        let signature = Signature::method::<A, R>(meta.as_ref()).with(generator);
+        self.0.add_meta_method_mut(meta, move |lua, this, args: MultiValue| {
+            method(lua, this, signature.from_lua_args::<A>(args, lua)?)
+        })
     }
 
-    fn add_meta_function_mut<A, R, F>(&mut self, meta: MetaMethod, function: F)
+    fn add_meta_function_mut<A, R, F>(&mut self, meta: MetaMethod, mut function: F)
     where
         A: FromLuaMulti + TypedMultiValue,
         R: IntoLuaMulti + TypedMultiValue,
         F: 'static + MaybeSend + FnMut(&Lua, A) -> mlua::Result<R>,
     {
-        self.0.add_meta_function_mut(meta, function)
+        let signature = Signature::function::<A, R>(meta.as_ref());
+        self.0.add_meta_function_mut(meta, move |lua, args: MultiValue| {
+            function(lua, signature.from_lua_args::<A>(args, lua)?)
+        })
     }
 
     fn add_meta_function_mut_with<A, R, F, G>(
         &mut self,
         meta: MetaMethod,
-        function: F,
-        _generator: G,
+        mut function: F,
+        generator: G,
     ) where
         A: FromLuaMulti + TypedMultiValue,
         R: IntoLuaMulti + TypedMultiValue,
         F: 'static + MaybeSend + FnMut(&Lua, A) -> mlua::Result<R>,
         G: Fn(&mut FunctionBuilder<A, R>),
     {
-        self.0.add_meta_function_mut(meta, function)
+        let signature = Signature::function::<A, R>(meta.as_ref()).with(generator);
+        self.0.add_meta_function_mut(meta, move |lua, args: MultiValue| {
+            function(lua, signature.from_lua_args::<A>(args, lua)?)
+        })
     }
 }
@@ -1,10 +1,10 @@
-use std::{borrow::Cow, marker::PhantomData};
+use std::{borrow::Cow, marker::PhantomData, sync::Arc};
 
-use mlua::{FromLua, FromLuaMulti, Function, IntoLua, IntoLuaMulti, Lua, Value};
+use mlua::{FromLua, FromLuaMulti, Function, IntoLua, IntoLuaMulti, Lua, MultiValue, Value};
 
 use crate::MaybeSend;
 
-use super::{Type, Typed, TypedMultiValue};
+use super::{generator::{DefinitionWriter, FunctionBuilder}, Type, Typed, TypedMultiValue};
 
 /// A function parameter type representation
 #[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord)]
@@ -28,6 +28,44 @@ impl Param {
         self.doc = Some(doc.into());
         self
     }
+
+    /// Describe the parameter as a callback with named and documented parameters
+    ///
+    /// The callback's docs, along with the docs of it's parameters, are used as the parameter's
+    /// doc comment if it doesn't have one.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mlua_extras::{mlua::Function, typed::{generator::Definition, TypedFunction}};
+    ///
+    /// let definition = Definition::start()
+    ///     .function_with::<(String, Function), ()>("on_event", (), |func| {
+    ///         func.param(0, |param| { param.set_name("name"); });
+    ///         func.param(1, |param| {
+    ///             param.set_name("handler").callback::<(String, i32), bool, _>(|callback| {
+    ///                 callback.document("Return `true` to stop other handlers");
+    ///                 callback.param(0, |param| { param.set_name("evt").set_doc("Name of the event"); });
+    ///                 callback.param(1, |param| { param.set_name("ctx"); });
+    ///             });
+    ///         });
+    ///     })
+    ///     .finish();
+    /// ```
+    pub fn callback<A, R, F>(&mut self, generator: F) -> &mut Self
+    where
+        A: TypedMultiValue,
+        R: TypedMultiValue,
+        F: Fn(&mut FunctionBuilder<A, R>),
+    {
+        let mut builder = FunctionBuilder::<A, R>::default();
+        generator(&mut builder);
+        if self.doc.is_none() {
+            self.doc = builder.callback_doc().map(Cow::Owned);
+        }
+        self.ty = builder.into_type();
+        self
+    }
 }
 
 /// A function parameter type representation
@@ -44,6 +82,24 @@ impl Return {
         self.doc = Some(doc.into());
         self
     }
+
+    /// Describe the returned value as a function with named and documented parameters
+    ///
+    /// Same as [`Param::callback`]
+    pub fn callback<A, R, F>(&mut self, generator: F) -> &mut Self
+    where
+        A: TypedMultiValue,
+        R: TypedMultiValue,
+        F: Fn(&mut FunctionBuilder<A, R>),
+    {
+        let mut builder = FunctionBuilder::<A, R>::default();
+        generator(&mut builder);
+        if self.doc.is_none() {
+            self.doc = builder.callback_doc().map(Cow::Owned);
+        }
+        self.ty = builder.into_type();
+        self
+    }
 }
 
 impl<I: Into<Cow<'static, str>>> From<(I, Type)> for Param {
@@ -66,6 +122,127 @@ impl From<Type> for Param {
     }
 }
 
+impl From<Type> for Return {
+    fn from(value: Type) -> Self {
+        Return {
+            doc: None,
+            ty: value,
+        }
+    }
+}
+
+/// Declared signature of a typed function that is registered with lua.
+///
+/// Used to convert the arguments passed from lua so that conversion failures report the declared
+/// parameter name and type instead of only the argument's position.
+#[derive(Debug, Clone)]
+pub(crate) struct Signature {
+    name: String,
+    params: Vec<Param>,
+    returns: Vec<Return>,
+    method: bool,
+}
+
+impl Signature {
+    /// Signature of a function using the type information from it's params and returns
+    pub(crate) fn function<A: TypedMultiValue, R: TypedMultiValue>(name: impl AsRef<str>) -> Self {
+        Self {
+            name: name.as_ref().to_string(),
+            params: A::get_types_as_params(),
+            returns: R::get_types_as_returns(),
+            method: false,
+        }
+    }
+
+    /// Signature of a method. Arguments are offset by one to account for `self`
+    pub(crate) fn method<A: TypedMultiValue, R: TypedMultiValue>(name: impl AsRef<str>) -> Self {
+        Self {
+            method: true,
+            ..Self::function::<A, R>(name)
+        }
+    }
+
+    /// Apply the additional param and return information from a [`FunctionBuilder`] generator
+    pub(crate) fn with<A, R, G>(mut self, generator: G) -> Self
+    where
+        A: TypedMultiValue,
+        R: TypedMultiValue,
+        G: Fn(&mut FunctionBuilder<A, R>),
+    {
+        let mut builder = FunctionBuilder::<A, R>::default();
+        generator(&mut builder);
+        self.params = builder.params;
+        self.returns = builder.returns;
+        self
+    }
+
+    /// Convert the arguments passed from lua into the function's params
+    ///
+    /// Conversion failures are reported as [`BadArgument`][mlua::Error::BadArgument] errors with
+    /// the declared param name and a cause of `expected string, got nil` followed by the declared
+    /// signature of the function.
+    pub(crate) fn from_lua_args<A: FromLuaMulti>(&self, args: MultiValue, lua: &Lua) -> mlua::Result<A> {
+        let offset = if self.method { 2 } else { 1 };
+        // Only the type names are kept so the values themselves aren't cloned on every call
+        let got = args.iter().map(Value::type_name).collect::<Vec<_>>();
+        A::from_lua_args(args, offset, Some(self.name.as_str()), lua).map_err(|err| match err {
+            mlua::Error::BadArgument { to, pos, name, cause } => {
+                let index = pos.saturating_sub(offset);
+                let got = got.get(index).copied().unwrap_or("no value");
+                // Extra arguments are all matched against a trailing variadic param
+                let param = self
+                    .params
+                    .get(index)
+                    .or_else(|| self.params.last().filter(|p| p.name.as_deref() == Some("...")));
+
+                let cause = match param.and_then(|p| DefinitionWriter::type_signature(&p.ty).ok()) {
+                    Some(expected) => mlua::Error::runtime(format!(
+                        "expected {expected}, got {got}\n  signature: {self}"
+                    )),
+                    None => mlua::Error::WithContext {
+                        context: format!("signature: {self}"),
+                        cause,
+                    },
+                };
+                mlua::Error::BadArgument {
+                    to,
+                    pos,
+                    name: param.and_then(|p| p.name.as_deref()).map(str::to_string).or(name),
+                    cause: Arc::new(cause),
+                }
+            }
+            other => other,
+        })
+    }
+}
+
+impl std::fmt::Display for Signature {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut params = Vec::new();
+        if self.method {
+            params.push("self".to_string());
+        }
+        for (i, param) in self.params.iter().enumerate() {
+            let name = param.name.as_ref().map(|v| v.to_string()).unwrap_or(format!("param{i}"));
+            params.push(match DefinitionWriter::type_signature(&param.ty) {
+                Ok(ty) => format!("{name}: {ty}"),
+                Err(_) => name,
+            });
+        }
+
+        write!(f, "{}({})", self.name, params.join(", "))?;
+        let returns = self
+            .returns
+            .iter()
+            .filter_map(|v| DefinitionWriter::type_signature(&v.ty).ok())
+            .collect::<Vec<_>>();
+        if !returns.is_empty() {
+            write!(f, ": {}", returns.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
 /// Used to purely get function type information without converting it to anything
 /// else.
 pub trait IntoTypedFunction<Params: TypedMultiValue, Response: TypedMultiValue> {
@@ -129,6 +306,76 @@ where
     }
 }
 
+/// Used to purely get async function type information without converting it to anything else.
+///
+/// Same as [`IntoTypedFunction`] for async rust functions
+pub trait IntoTypedAsyncFunction<Params: TypedMultiValue, Response: TypedMultiValue> {
+    fn into_typed_async_function(
+        self,
+        lua: &Lua,
+    ) -> mlua::Result<TypedAsyncFunction<Params, Response>>;
+}
+
+#[cfg(feature = "async")]
+impl<F, FR, Params, Response> IntoTypedAsyncFunction<Params, Response> for F
+where
+    Params: TypedMultiValue + FromLuaMulti,
+    Response: TypedMultiValue + IntoLuaMulti,
+    F: Fn(Lua, Params) -> FR + MaybeSend + 'static,
+    FR: std::future::Future<Output = mlua::Result<Response>> + MaybeSend + 'static,
+{
+    fn into_typed_async_function(
+        self,
+        lua: &Lua,
+    ) -> mlua::Result<TypedAsyncFunction<Params, Response>> {
+        Ok(TypedAsyncFunction(TypedFunction {
+            inner: lua.create_async_function(self)?,
+            _p: PhantomData,
+            _r: PhantomData,
+        }))
+    }
+}
+
+impl<Params, Response> IntoTypedAsyncFunction<Params, Response> for Function
+where
+    Params: TypedMultiValue + FromLuaMulti,
+    Response: TypedMultiValue + IntoLuaMulti,
+{
+    fn into_typed_async_function(
+        self,
+        lua: &Lua,
+    ) -> mlua::Result<TypedAsyncFunction<Params, Response>> {
+        Ok(TypedAsyncFunction(self.into_typed_function(lua)?))
+    }
+}
+
+impl<Params, Response> IntoTypedAsyncFunction<Params, Response>
+    for &TypedAsyncFunction<Params, Response>
+where
+    Params: TypedMultiValue + FromLuaMulti,
+    Response: TypedMultiValue + IntoLuaMulti,
+{
+    fn into_typed_async_function(
+        self,
+        lua: &Lua,
+    ) -> mlua::Result<TypedAsyncFunction<Params, Response>> {
+        Ok(TypedAsyncFunction(IntoTypedFunction::into_typed_function(&self.0, lua)?))
+    }
+}
+
+impl<Params, Response> IntoTypedAsyncFunction<Params, Response> for ()
+where
+    Params: TypedMultiValue + FromLuaMulti,
+    Response: TypedMultiValue + IntoLuaMulti,
+{
+    fn into_typed_async_function(
+        self,
+        lua: &Lua,
+    ) -> mlua::Result<TypedAsyncFunction<Params, Response>> {
+        Ok(TypedAsyncFunction(self.into_typed_function(lua)?))
+    }
+}
+
 /// Helper to bake the type information for a lua [`Function`][mlua::Function]. This makes repeated
 /// calls to the [`Function`][mlua::Function]'s [`call`][mlua::Function::call] all the same with
 /// enforced arguments and return types.
@@ -163,6 +410,11 @@ where
         self.inner.call::<Response>(params).unwrap()
     }
 
+    /// Get the underlying lua [`Function`][mlua::Function]
+    pub fn as_function(&self) -> &Function {
+        &self.inner
+    }
+
     /// Create a typed function from a rust function.
     ///
     /// This will call [`Lua::create_function`] under the hood
@@ -178,8 +430,103 @@ where
             _r: PhantomData,
         })
     }
+
+    /// Same as [Function::call_async] but with the param and return
+    /// types already specified
+    #[cfg(feature = "async")]
+    pub async fn call_async(&self, params: Params) -> mlua::Result<Response> {
+        self.inner.call_async::<Response>(params).await
+    }
+
+    /// Create a typed function from an async rust function.
+    ///
+    /// This will call [`Lua::create_async_function`] under the hood. The returned
+    /// [`TypedAsyncFunction`] is annotated with `@async` when it is used in definitions.
+    #[cfg(feature = "async")]
+    pub fn from_async<F, FR>(
+        lua: &Lua,
+        func: F,
+    ) -> mlua::Result<TypedAsyncFunction<Params, Response>>
+    where
+        Params: TypedMultiValue + FromLuaMulti,
+        Response: TypedMultiValue + IntoLuaMulti,
+        F: Fn(Lua, Params) -> FR + MaybeSend + 'static,
+        FR: std::future::Future<Output = mlua::Result<Response>> + MaybeSend + 'static,
+    {
+        func.into_typed_async_function(lua)
+    }
+
+    /// Same as [Function::bind] but with the leading param type already specified.
+    ///
+    /// The returned function takes the remaining params. Call `bind` again to bind more of the
+    /// leading params.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mlua_extras::{mlua::Lua, typed::TypedFunction};
+    ///
+    /// let lua = Lua::new();
+    /// let add = lua
+    ///     .load("function(a, b) return a + b end")
+    ///     .eval::<TypedFunction<(i32, i32), i32>>()?;
+    ///
+    /// let add_one = add.bind(1)?;
+    /// assert_eq!(add_one.call((2,))?, 3);
+    /// ```
+    pub fn bind(
+        &self,
+        param: Params::First,
+    ) -> mlua::Result<TypedFunction<Params::Rest, Response>>
+    where
+        Params: SplitParams,
+        Params::First: IntoLua,
+        Params::Rest: TypedMultiValue,
+    {
+        Ok(TypedFunction {
+            inner: self.inner.bind(param)?,
+            _p: PhantomData,
+            _r: PhantomData,
+        })
+    }
 }
 
+/// Split the leading param from a tuple of params
+///
+/// Used by [`TypedFunction::bind`] to partially apply a function's params
+pub trait SplitParams {
+    /// The leading param
+    type First;
+    /// The remaining params
+    type Rest;
+}
+
+macro_rules! impl_split_params {
+    ($first: ident $($rest: ident)*) => {
+        impl<$first, $($rest,)*> SplitParams for ($first, $($rest,)*) {
+            type First = $first;
+            type Rest = ($($rest,)*);
+        }
+    };
+}
+
+impl_split_params!(A B C D E F G H I J K L M N O P);
+impl_split_params!(A B C D E F G H I J K L M N O);
+impl_split_params!(A B C D E F G H I J K L M N);
+impl_split_params!(A B C D E F G H I J K L M);
+impl_split_params!(A B C D E F G H I J K L);
+impl_split_params!(A B C D E F G H I J K);
+impl_split_params!(A B C D E F G H I J);
+impl_split_params!(A B C D E F G H I);
+impl_split_params!(A B C D E F G H);
+impl_split_params!(A B C D E F G);
+impl_split_params!(A B C D E F);
+impl_split_params!(A B C D E);
+impl_split_params!(A B C D);
+impl_split_params!(A B C);
+impl_split_params!(A B);
+impl_split_params!(A);
+
 impl<Params, Response> FromLua for TypedFunction<Params, Response>
 where
     Params: TypedMultiValue,
@@ -213,6 +560,78 @@ where
         Type::Function {
             params: Params::get_types_as_params(),
             returns: Response::get_types().into_iter().map(|ty| Return { doc: None, ty }).collect(),
+            is_async: false,
+        }
+    }
+}
+
+/// A [`TypedFunction`] that was created from an async rust function
+///
+/// The function's type is annotated with `@async` in definitions. Derefs to the
+/// [`TypedFunction`] to call it.
+pub struct TypedAsyncFunction<Params, Response>(TypedFunction<Params, Response>)
+where
+    Params: TypedMultiValue,
+    Response: TypedMultiValue;
+
+impl<Params, Response> TypedAsyncFunction<Params, Response>
+where
+    Params: TypedMultiValue,
+    Response: TypedMultiValue,
+{
+    /// Unwrap the underlying [`TypedFunction`]
+    pub fn into_inner(self) -> TypedFunction<Params, Response> {
+        self.0
+    }
+}
+
+impl<Params, Response> std::ops::Deref for TypedAsyncFunction<Params, Response>
+where
+    Params: TypedMultiValue,
+    Response: TypedMultiValue,
+{
+    type Target = TypedFunction<Params, Response>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<Params, Response> FromLua for TypedAsyncFunction<Params, Response>
+where
+    Params: TypedMultiValue,
+    Response: TypedMultiValue,
+{
+    fn from_lua(value: Value, lua: &Lua) -> mlua::prelude::LuaResult<Self> {
+        Ok(Self(FromLua::from_lua(value, lua)?))
+    }
+}
+
+impl<Params, Response> IntoLua for TypedAsyncFunction<Params, Response>
+where
+    Params: TypedMultiValue,
+    Response: TypedMultiValue,
+{
+    fn into_lua(self, lua: &Lua) -> mlua::prelude::LuaResult<Value> {
+        self.0.into_lua(lua)
+    }
+}
+
+impl<Params, Response> Typed for TypedAsyncFunction<Params, Response>
+where
+    Params: TypedMultiValue,
+    Response: TypedMultiValue,
+{
+    fn ty() -> Type {
+        match TypedFunction::<Params, Response>::ty() {
+            Type::Function {
+                params, returns, ..
+            } => Type::Function {
+                params,
+                returns,
+                is_async: true,
+            },
+            other => other,
         }
     }
 }
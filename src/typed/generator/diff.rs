@@ -0,0 +1,669 @@
+use std::{borrow::Cow, collections::BTreeMap};
+
+use crate::typed::{
+    function::Return, Field, Func, Param, Type, TypedClassBuilder, TypedModuleBuilder,
+};
+
+use super::{DefinitionWriter, Definitions};
+
+/// What happened to an item between two definition snapshots
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, strum::AsRefStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    /// The type now accepts more values. i.e. `string` -> `string|nil`
+    Widened,
+    /// The type now accepts less values. i.e. `string|nil` -> `string`
+    Narrowed,
+    /// The type was replaced with an unrelated type
+    Changed,
+}
+
+/// A single difference between two definition snapshots
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Change {
+    /// Path to the item. i.e. `Player`, `Player.name`, or `Player:heal`
+    pub path: String,
+    /// What the item is. i.e. `class`, `field`, `method`, `param 'name'`, `return #1`
+    pub item: String,
+    pub kind: ChangeKind,
+    /// Whether scripts written against the old definitions can break
+    pub breaking: bool,
+    /// Type signature before the change
+    pub old: Option<String>,
+    /// Type signature after the change
+    pub new: Option<String>,
+}
+
+impl std::fmt::Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "[{}] {} {} {}",
+            if self.breaking {
+                "breaking"
+            } else {
+                "non-breaking"
+            },
+            self.kind.as_ref(),
+            self.item,
+            self.path
+        )?;
+        match (&self.old, &self.new) {
+            (Some(old), Some(new)) => write!(f, ": {old} -> {new}"),
+            (Some(ty), None) | (None, Some(ty)) => write!(f, ": {ty}"),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+/// Semantic difference between two [`Definitions`] snapshots
+///
+/// Changes are classified as breaking when scripts written against the old definitions may no
+/// longer work:
+///
+/// - Removing classes, fields, functions, params, returns, or enum variants is breaking
+/// - Adding is non-breaking, except for adding a required param
+/// - Params may be widened but not narrowed since scripts pass them
+/// - Returns and values may be narrowed but not widened since scripts read them
+/// - Fields and aliases are both read and written by scripts so any change to their type is
+///   breaking
+///
+/// Types are compared with [`Type::is_assignable_to`], so `integer` -> `number` is a widening.
+///
+/// # Example
+///
+/// ```
+/// use mlua_extras::typed::generator::{ApiDiff, Definition, Definitions};
+///
+/// let old = Definitions::start()
+///     .define("init", Definition::start().value::<String>("name"))
+///     .finish();
+/// let new = Definitions::start()
+///     .define("init", Definition::start().value::<Option<String>>("name"))
+///     .finish();
+///
+/// let diff = ApiDiff::between(&old, &new);
+/// assert!(diff.is_breaking());
+/// println!("{diff}");
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ApiDiff {
+    pub changes: Vec<Change>,
+}
+
+impl ApiDiff {
+    /// Compare two definition snapshots
+    ///
+    /// Entries are matched by name across all definition groups, so moving an entry to another
+    /// group is not a change.
+    pub fn between(old: &Definitions, new: &Definitions) -> Self {
+        let mut diff = Self::default();
+        diff.entries(&Self::flatten(old), &Self::flatten(new));
+        diff.changes.sort();
+        diff
+    }
+
+    /// Check if any of the changes are breaking
+    pub fn is_breaking(&self) -> bool {
+        self.changes.iter().any(|change| change.breaking)
+    }
+
+    /// Check if there are no changes
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Only the breaking changes
+    pub fn breaking(&self) -> impl Iterator<Item = &Change> {
+        self.changes.iter().filter(|change| change.breaking)
+    }
+
+    /// Only the non-breaking changes
+    pub fn non_breaking(&self) -> impl Iterator<Item = &Change> {
+        self.changes.iter().filter(|change| !change.breaking)
+    }
+
+    /// Machine readable report
+    ///
+    /// ```json
+    /// {
+    ///   "breaking": true,
+    ///   "changes": [
+    ///     { "path": "Player:heal", "item": "method", "kind": "removed", "breaking": true, "old": "fun(amount: integer)", "new": null }
+    ///   ]
+    /// }
+    /// ```
+    pub fn to_json(&self) -> String {
+        let string = |value: Option<&str>| match value {
+            Some(value) => json_string(value),
+            None => "null".to_string(),
+        };
+
+        let changes = self
+            .changes
+            .iter()
+            .map(|change| {
+                format!(
+                    "    {{ \"path\": {}, \"item\": {}, \"kind\": {}, \"breaking\": {}, \"old\": {}, \"new\": {} }}",
+                    json_string(&change.path),
+                    json_string(&change.item),
+                    json_string(change.kind.as_ref()),
+                    change.breaking,
+                    string(change.old.as_deref()),
+                    string(change.new.as_deref()),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        if changes.is_empty() {
+            format!(
+                "{{\n  \"breaking\": {},\n  \"changes\": []\n}}",
+                self.is_breaking()
+            )
+        } else {
+            format!(
+                "{{\n  \"breaking\": {},\n  \"changes\": [\n{}\n  ]\n}}",
+                self.is_breaking(),
+                changes.join(",\n")
+            )
+        }
+    }
+
+    fn flatten<'a>(definitions: &'a Definitions) -> BTreeMap<&'a str, &'a Type> {
+        let mut entries = BTreeMap::new();
+        for (_, definition) in definitions.iter() {
+            for entry in definition.entries.iter() {
+                entries.entry(entry.name.as_ref()).or_insert(&entry.ty);
+            }
+        }
+        entries
+    }
+
+    fn push(
+        &mut self,
+        path: impl Into<String>,
+        item: impl Into<String>,
+        kind: ChangeKind,
+        breaking: bool,
+        old: Option<&Type>,
+        new: Option<&Type>,
+    ) {
+        self.changes.push(Change {
+            path: path.into(),
+            item: item.into(),
+            kind,
+            breaking,
+            old: old.map(signature),
+            new: new.map(signature),
+        });
+    }
+
+    fn entries(&mut self, old: &BTreeMap<&str, &Type>, new: &BTreeMap<&str, &Type>) {
+        for (name, old_ty) in old.iter() {
+            match new.get(name) {
+                Some(new_ty) => self.entry(name, old_ty, new_ty),
+                None => self.push(
+                    *name,
+                    item_name(old_ty),
+                    ChangeKind::Removed,
+                    true,
+                    Some(*old_ty),
+                    None,
+                ),
+            }
+        }
+
+        for (name, new_ty) in new.iter() {
+            if !old.contains_key(name) {
+                self.push(
+                    *name,
+                    item_name(new_ty),
+                    ChangeKind::Added,
+                    false,
+                    None,
+                    Some(*new_ty),
+                );
+            }
+        }
+    }
+
+    fn entry(&mut self, path: &str, old: &Type, new: &Type) {
+        match (old, new) {
+            (Type::Class(old), Type::Class(new)) => self.class(path, old, new),
+            (Type::Module(old), Type::Module(new)) => self.module(path, old, new),
+            (
+                Type::Function {
+                    params,
+                    returns,
+                    is_async,
+                },
+                Type::Function {
+                    params: new_params,
+                    returns: new_returns,
+                    is_async: new_async,
+                },
+            ) => self.signature(
+                path,
+                "function",
+                params,
+                returns,
+                *is_async,
+                new_params,
+                new_returns,
+                *new_async,
+            ),
+            (Type::Enum(_, old_variants), Type::Enum(_, new_variants)) => {
+                self.variants(path, old_variants, new_variants)
+            }
+            (Type::Value(old), Type::Value(new)) => {
+                self.value(path, "value", old, new, Some(ChangeKind::Narrowed))
+            }
+            (Type::Alias(old), Type::Alias(new)) => self.value(path, "alias", old, new, None),
+            (old, new) if old != new => self.push(
+                path,
+                item_name(new),
+                ChangeKind::Changed,
+                true,
+                Some(old),
+                Some(new),
+            ),
+            _ => {}
+        }
+    }
+
+    /// Record a change to the type of an item
+    ///
+    /// `allowed` is the kind of change that doesn't break scripts, `None` if the item is both read
+    /// and written so any change is breaking
+    fn value(
+        &mut self,
+        path: &str,
+        item: impl Into<String>,
+        old: &Type,
+        new: &Type,
+        allowed: Option<ChangeKind>,
+    ) {
+        if old == new {
+            return;
+        }
+        if let Some(kind) = compare(old, new) {
+            self.push(
+                path,
+                item,
+                kind,
+                Some(kind) != allowed,
+                Some(old),
+                Some(new),
+            );
+        }
+    }
+
+    fn variants(&mut self, path: &str, old: &[Type], new: &[Type]) {
+        for variant in old.iter().filter(|v| !new.contains(v)) {
+            self.push(
+                path,
+                "variant",
+                ChangeKind::Removed,
+                true,
+                Some(variant),
+                None,
+            );
+        }
+        for variant in new.iter().filter(|v| !old.contains(v)) {
+            self.push(
+                path,
+                "variant",
+                ChangeKind::Added,
+                false,
+                None,
+                Some(variant),
+            );
+        }
+    }
+
+    fn fields(
+        &mut self,
+        path: &str,
+        separator: &str,
+        old: &BTreeMap<Cow<'static, str>, Field>,
+        new: &BTreeMap<Cow<'static, str>, Field>,
+    ) {
+        for (name, field) in old.iter() {
+            let path = format!("{path}{separator}{name}");
+            match new.get(name) {
+                Some(new) => self.value(&path, "field", &field.ty, &new.ty, None),
+                None => self.push(
+                    path,
+                    "field",
+                    ChangeKind::Removed,
+                    true,
+                    Some(&field.ty),
+                    None,
+                ),
+            }
+        }
+
+        for (name, field) in new.iter() {
+            if !old.contains_key(name) {
+                let path = format!("{path}{separator}{name}");
+                self.push(
+                    path,
+                    "field",
+                    ChangeKind::Added,
+                    false,
+                    None,
+                    Some(&field.ty),
+                );
+            }
+        }
+    }
+
+    fn functions(
+        &mut self,
+        path: &str,
+        separator: &str,
+        item: &str,
+        old: &BTreeMap<Cow<'static, str>, Func>,
+        new: &BTreeMap<Cow<'static, str>, Func>,
+    ) {
+        for (name, func) in old.iter() {
+            let path = format!("{path}{separator}{name}");
+            match new.get(name) {
+                Some(new) => self.signature(
+                    &path,
+                    item,
+                    &func.params,
+                    &func.returns,
+                    func.is_async,
+                    &new.params,
+                    &new.returns,
+                    new.is_async,
+                ),
+                None => self.push(
+                    path,
+                    item,
+                    ChangeKind::Removed,
+                    true,
+                    Some(&func_type(func)),
+                    None,
+                ),
+            }
+        }
+
+        for (name, func) in new.iter() {
+            if !old.contains_key(name) {
+                let path = format!("{path}{separator}{name}");
+                self.push(
+                    path,
+                    item,
+                    ChangeKind::Added,
+                    false,
+                    None,
+                    Some(&func_type(func)),
+                );
+            }
+        }
+    }
+
+    fn class(&mut self, path: &str, old: &TypedClassBuilder, new: &TypedClassBuilder) {
+        self.fields(path, ".", &old.fields, &new.fields);
+        self.fields(path, ".", &old.static_fields, &new.static_fields);
+        self.fields(path, ".__metatable.", &old.meta_fields, &new.meta_fields);
+        self.fields(
+            path,
+            "",
+            &index_fields(&old.index_fields),
+            &index_fields(&new.index_fields),
+        );
+        self.functions(path, ".", "function", &old.functions, &new.functions);
+        self.functions(path, ":", "method", &old.methods, &new.methods);
+        self.functions(
+            path,
+            ".__metatable.",
+            "function",
+            &old.meta_functions,
+            &new.meta_functions,
+        );
+        self.functions(
+            path,
+            ".__metatable:",
+            "method",
+            &old.meta_methods,
+            &new.meta_methods,
+        );
+    }
+
+    fn module(&mut self, path: &str, old: &TypedModuleBuilder, new: &TypedModuleBuilder) {
+        self.fields(path, ".", &old.fields, &new.fields);
+        self.fields(path, ".__metatable.", &old.meta_fields, &new.meta_fields);
+        self.fields(
+            path,
+            "",
+            &index_fields(&old.index_fields),
+            &index_fields(&new.index_fields),
+        );
+        self.functions(path, ".", "function", &old.functions, &new.functions);
+        self.functions(path, ":", "method", &old.methods, &new.methods);
+        self.functions(
+            path,
+            ".__metatable.",
+            "function",
+            &old.meta_functions,
+            &new.meta_functions,
+        );
+        self.functions(
+            path,
+            ".__metatable:",
+            "method",
+            &old.meta_methods,
+            &new.meta_methods,
+        );
+
+        for (name, module) in old.nested_modules.iter() {
+            let path = format!("{path}.{name}");
+            match new.nested_modules.get(name) {
+                Some(new) => self.module(&path, module, new),
+                None => self.push(path, "module", ChangeKind::Removed, true, None, None),
+            }
+        }
+        for name in new.nested_modules.keys() {
+            if !old.nested_modules.contains_key(name) {
+                self.push(
+                    format!("{path}.{name}"),
+                    "module",
+                    ChangeKind::Added,
+                    false,
+                    None,
+                    None,
+                );
+            }
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn signature(
+        &mut self,
+        path: &str,
+        item: &str,
+        old_params: &[Param],
+        old_returns: &[Return],
+        old_async: bool,
+        new_params: &[Param],
+        new_returns: &[Return],
+        new_async: bool,
+    ) {
+        if !old_async && new_async {
+            self.push(
+                path,
+                format!("async {item}"),
+                ChangeKind::Changed,
+                true,
+                None,
+                None,
+            );
+        }
+
+        for i in 0..old_params.len().max(new_params.len()) {
+            let param = |param: &Param| match param.name.as_deref() {
+                Some(name) => format!("param '{name}'"),
+                None => format!("param #{}", i + 1),
+            };
+
+            match (old_params.get(i), new_params.get(i)) {
+                // Params can accept more values but not less
+                (Some(old), Some(new)) => self.value(
+                    path,
+                    param(new),
+                    &old.ty,
+                    &new.ty,
+                    Some(ChangeKind::Widened),
+                ),
+                (Some(old), None) => self.push(
+                    path,
+                    param(old),
+                    ChangeKind::Removed,
+                    true,
+                    Some(&old.ty),
+                    None,
+                ),
+                (None, Some(new)) => {
+                    // Scripts that don't pass the new param still work if it is optional
+                    let breaking = !is_optional(&new.ty);
+                    self.push(
+                        path,
+                        param(new),
+                        ChangeKind::Added,
+                        breaking,
+                        None,
+                        Some(&new.ty),
+                    )
+                }
+                _ => {}
+            }
+        }
+
+        for i in 0..old_returns.len().max(new_returns.len()) {
+            let item = format!("return #{}", i + 1);
+            match (old_returns.get(i), new_returns.get(i)) {
+                // Returns can produce less values but not more
+                (Some(old), Some(new)) => {
+                    self.value(path, item, &old.ty, &new.ty, Some(ChangeKind::Narrowed))
+                }
+                (Some(old), None) => {
+                    self.push(path, item, ChangeKind::Removed, true, Some(&old.ty), None)
+                }
+                (None, Some(new)) => {
+                    self.push(path, item, ChangeKind::Added, false, None, Some(&new.ty))
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Human readable report with breaking changes listed first
+impl std::fmt::Display for ApiDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.changes.is_empty() {
+            return writeln!(f, "no changes");
+        }
+
+        let breaking = self.breaking().collect::<Vec<_>>();
+        let non_breaking = self.non_breaking().collect::<Vec<_>>();
+
+        if !breaking.is_empty() {
+            writeln!(f, "Breaking changes ({}):", breaking.len())?;
+            for change in breaking {
+                writeln!(f, "  {change}")?;
+            }
+        }
+
+        if !non_breaking.is_empty() {
+            writeln!(f, "Non-breaking changes ({}):", non_breaking.len())?;
+            for change in non_breaking {
+                writeln!(f, "  {change}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn signature(ty: &Type) -> String {
+    DefinitionWriter::type_signature(ty).unwrap_or_else(|_| ty.as_ref().to_lowercase())
+}
+
+/// Index signatures keyed by how they are written. i.e. `[string]`
+fn index_fields(fields: &BTreeMap<Type, Field>) -> BTreeMap<Cow<'static, str>, Field> {
+    fields
+        .iter()
+        .map(|(key, field)| (format!("[{}]", signature(key)).into(), field.clone()))
+        .collect()
+}
+
+fn func_type(func: &Func) -> Type {
+    Type::Function {
+        params: func.params.clone(),
+        returns: func.returns.clone(),
+        is_async: func.is_async,
+    }
+}
+
+fn item_name(ty: &Type) -> &'static str {
+    match ty {
+        Type::Class(_) => "class",
+        Type::Module(_) => "module",
+        Type::Function { .. } => "function",
+        Type::Enum(_, _) => "enum",
+        Type::Alias(_) => "alias",
+        _ => "value",
+    }
+}
+
+fn members(ty: &Type) -> Vec<&Type> {
+    match ty {
+        Type::Union(types) => types.iter().flat_map(members).collect(),
+        other => Vec::from([other]),
+    }
+}
+
+/// Compare two types by which values they accept, `None` if they accept the same values
+fn compare(old: &Type, new: &Type) -> Option<ChangeKind> {
+    // `any` is assignable to and from everything but is still wider than any other type
+    let any = |ty: &Type| matches!(ty.clone().normalize(), Type::Single(name) if name == "any");
+
+    match (old.is_assignable_to(new), new.is_assignable_to(old)) {
+        (true, true) if any(new) && !any(old) => Some(ChangeKind::Widened),
+        (true, true) if any(old) && !any(new) => Some(ChangeKind::Narrowed),
+        (true, true) => None,
+        (true, false) => Some(ChangeKind::Widened),
+        (false, true) => Some(ChangeKind::Narrowed),
+        (false, false) => Some(ChangeKind::Changed),
+    }
+}
+
+fn is_optional(ty: &Type) -> bool {
+    members(ty).into_iter().any(|ty| {
+        matches!(ty, Type::Variadic(_))
+            || matches!(ty, Type::Single(name) if name == "nil" || name == "any")
+    })
+}
+
+fn json_string(value: &str) -> String {
+    let mut result = String::with_capacity(value.len() + 2);
+    result.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            c if (c as u32) < 0x20 => result.push_str(&format!("\\u{:04x}", c as u32)),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
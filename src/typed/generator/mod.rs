@@ -2,10 +2,19 @@ use std::{
     borrow::Cow, marker::PhantomData, slice::{Iter, IterMut}, vec::IntoIter
 };
 
-use super::{function::{IntoTypedFunction, Return}, Param, Type, Typed, TypedClassBuilder, TypedModule, TypedModuleBuilder, TypedMultiValue, TypedUserData};
+use super::{function::{IntoTypedAsyncFunction, IntoTypedFunction, Return}, short_type_name, Param, Type, Typed, TypedClassBuilder, TypedModule, TypedModuleBuilder, TypedMultiValue, TypedUserData};
 
+mod diff;
+mod parser;
+mod scanner;
 mod type_file;
-pub use type_file::DefinitionFileGenerator;
+mod type_registry;
+pub use diff::{ApiDiff, Change, ChangeKind};
+pub use parser::DefinitionParser;
+pub(crate) use parser::TypeParser;
+pub use scanner::Scanner;
+pub use type_file::{DefinitionFileGenerator, DefinitionWriter};
+pub use type_registry::{TypeIssue, TypeRegistry};
 
 /// Representation of a type that is defined in the definition file.
 ///
@@ -52,6 +61,7 @@ where
     pub doc: Option<Cow<'static, str>>,
     pub params: Vec<Param>,
     pub returns: Vec<Return>,
+    pub is_async: bool,
     _m: PhantomData<fn(Params) -> Returns>
 }
 
@@ -66,6 +76,7 @@ where
             doc: None,
             params: Params::get_types_as_params(),
             returns: Returns::get_types().into_iter().map(|ty| Return { doc: None, ty }).collect(),
+            is_async: false,
             _m: PhantomData, 
         }        
     }
@@ -82,6 +93,12 @@ where
         self
     }
 
+    /// Mark the function as async so it is annotated with `@async`
+    pub fn set_async(&mut self) -> &mut Self {
+        self.is_async = true;
+        self
+    }
+
     /// Update a parameter's information given it's position in the argument list
     pub fn param<F>(&mut self, index: usize, generator: F) -> &mut Self
     where
@@ -103,6 +120,40 @@ where
         }
         self
     }
+
+    /// Get the function type with the documented parameters and returns
+    pub fn into_type(self) -> Type {
+        Type::Function {
+            params: self.params,
+            returns: self.returns,
+            is_async: self.is_async,
+        }
+    }
+
+    /// Docs of the function followed by the docs of each named parameter and return
+    ///
+    /// Function types can't have doc comments so these are used for the param or return that is
+    /// the function instead
+    pub(crate) fn callback_doc(&self) -> Option<String> {
+        let mut docs = Vec::new();
+        if let Some(doc) = self.doc.as_deref() {
+            docs.push(doc.to_string());
+        }
+        for (i, param) in self.params.iter().enumerate() {
+            if let Some(doc) = param.doc.as_deref() {
+                match param.name.as_deref() {
+                    Some(name) => docs.push(format!("`{name}`: {doc}")),
+                    None => docs.push(format!("`param{i}`: {doc}")),
+                }
+            }
+        }
+        for (i, ret) in self.returns.iter().enumerate() {
+            if let Some(doc) = ret.doc.as_deref() {
+                docs.push(format!("returns #{}: {doc}", i + 1));
+            }
+        }
+        (!docs.is_empty()).then(|| docs.join("; "))
+    }
 }
 
 /// Builder for definition entries
@@ -146,13 +197,37 @@ impl<'def> DefinitionBuilder<'def> {
             name,
             Type::Function {
                 params: func.params,
-                returns: func.returns
+                returns: func.returns,
+                is_async: func.is_async,
             },
             func.doc,
         ));
         self
     }
 
+    /// Register a definition entry that is an async function type
+    ///
+    /// The function is annotated with `@async` in the generated definitions
+    pub fn async_function<Params, Returns>(
+        mut self,
+        name: impl Into<Cow<'def, str>>,
+        _: impl IntoTypedAsyncFunction<Params, Returns>,
+    ) -> Self
+    where
+        Params: TypedMultiValue,
+        Returns: TypedMultiValue,
+    {
+        self.entries.push(Entry::new(
+            name,
+            Type::Function {
+                params: Params::get_types_as_params(),
+                returns: Returns::get_types_as_returns(),
+                is_async: true,
+            },
+        ));
+        self
+    }
+
     /// Register a definition entry that is an alias type
     pub fn alias(mut self, name: impl Into<Cow<'static, str>>, ty: Type) -> Self {
         self.entries.push(Entry::new(name, Type::alias(ty)));
@@ -177,9 +252,8 @@ impl<'def> DefinitionBuilder<'def> {
     ///
     /// The name of the class is the same as the name of the type passed
     pub fn register_class<T: TypedUserData>(mut self) -> Self {
-        let name = std::any::type_name::<T>();
         self.entries.push(Entry::new(
-            name.rsplit_once("::").map(|v| v.1).unwrap_or(name),
+            short_type_name::<T>(),
             Type::class(TypedClassBuilder::new::<T>()),
         ));
         self
@@ -191,7 +265,7 @@ impl<'def> DefinitionBuilder<'def> {
         doc: Option<S>,
     ) -> Self {
         self.entries.push(Entry::new_with(
-            std::any::type_name::<T>(),
+            short_type_name::<T>(),
             Type::class(TypedClassBuilder::new::<T>()),
             doc,
         ));
@@ -287,6 +361,39 @@ impl<'def> DefinitionBuilder<'def> {
         Ok(self)
     }
 
+    /// Register a definition entry that is a record type, i.e. from `#[typed(record)]`
+    ///
+    /// The record is written as a class with a field for each of it's entries. Fields that are
+    /// an `Option<T>` are written as optional fields, i.e. `--- @field name? string`.
+    ///
+    /// Returns an error response of [`Error::RuntimeError`][mlua::Error::RuntimeError] if the type extracted was not [`Type::Struct`]
+    pub fn register_record<T: Typed>(self) -> mlua::Result<Self> {
+        self.register_record_with::<T, Cow<'def, str>>(None)
+    }
+
+    /// Same as [`register_record`][DefinitionBuilder::register_record] but with additional docs
+    pub fn register_record_with<T: Typed, S: Into<Cow<'def, str>>>(
+        mut self,
+        doc: Option<S>,
+    ) -> mlua::Result<Self> {
+        match T::ty() {
+            Type::Struct(fields) => {
+                self.entries.push(Entry::new_with(
+                    short_type_name::<T>(),
+                    Type::Struct(fields),
+                    doc,
+                ));
+            }
+            other => {
+                return Err(mlua::Error::runtime(format!(
+                    "expected record type was: {}",
+                    other.as_ref()
+                )))
+            }
+        }
+        Ok(self)
+    }
+
     /// Register a value that is available
     ///
     /// This can be a table, union/enum, literal, or any other value and it will be typed
@@ -359,6 +466,7 @@ impl<'def> DefinitionBuilder<'def> {
     pub fn finish(self) -> Definition<'def> {
         Definition {
             entries: self.entries,
+            require: None,
         }
     }
 }
@@ -367,11 +475,25 @@ impl<'def> DefinitionBuilder<'def> {
 ///
 /// This is commonly represented as an individual definition file
 #[derive(Default, Debug, Clone)]
+#[non_exhaustive]
 pub struct Definition<'def> {
     pub entries: Vec<Entry<'def>>,
+    /// Name that `require` resolves to the module entry with the same name
+    ///
+    /// The file is written with `--- @meta <name>` and returns the module instead of assigning it
+    /// to a global
+    pub require: Option<Cow<'def, str>>,
 }
 
 impl<'def> Definition<'def> {
+    /// Create a definition group from it's entries
+    pub fn new(entries: Vec<Entry<'def>>) -> Self {
+        Self {
+            entries,
+            require: None,
+        }
+    }
+
     pub fn start() -> DefinitionBuilder<'def> {
         DefinitionBuilder::default()
     }
@@ -384,6 +506,19 @@ impl<'def> Definition<'def> {
     pub fn iter(&self) -> Iter<'def, Entry<'_>> {
         self.entries.iter()
     }
+
+    /// Merge the entries from another definition into this one
+    ///
+    /// Entries that already exist in this definition take precedence over entries with the same
+    /// name in the other definition.
+    pub fn merge(mut self, other: impl Into<Definition<'def>>) -> Self {
+        for entry in other.into().entries {
+            if !self.entries.iter().any(|e| e.name == entry.name) {
+                self.entries.push(entry);
+            }
+        }
+        self
+    }
 }
 
 /// Generate definition entries and definition groups
@@ -406,6 +541,40 @@ impl<'def> DefinitionsBuilder<'def> {
         self
     }
 
+    /// Create a definition group for a module that is loaded with `require`, i.e. one added with
+    /// [`LuaExtras::preload_module`][crate::extras::LuaExtras::preload_module]
+    ///
+    /// Each nested module also gets its own group so it can be required by its dotted path, i.e.
+    /// `require("app.util")`
+    pub fn define_module<M: TypedModule>(
+        mut self,
+        name: impl Into<Cow<'def, str>>,
+    ) -> mlua::Result<Self> {
+        let module = TypedModuleBuilder::new::<M>()?;
+        self.define_required(name.into(), module);
+        Ok(self)
+    }
+
+    fn define_required(&mut self, name: Cow<'def, str>, module: TypedModuleBuilder) {
+        let nested = module
+            .nested_modules
+            .iter()
+            .map(|(child, module)| (format!("{name}.{child}"), module.clone()))
+            .collect::<Vec<_>>();
+
+        self.definitions.push((
+            name.clone(),
+            Definition {
+                entries: vec![Entry::new(name.clone(), Type::module(module))],
+                require: Some(name),
+            },
+        ));
+
+        for (name, module) in nested {
+            self.define_required(name.into(), module);
+        }
+    }
+
     /// Finish defining definition groups and collect them
     pub fn finish(self) -> Definitions<'def> {
         Definitions {
@@ -418,6 +587,7 @@ impl<'def> From<DefinitionBuilder<'def>> for Definition<'def> {
     fn from(value: DefinitionBuilder<'def>) -> Self {
         Definition {
             entries: value.entries,
+            require: None,
         }
     }
 }
@@ -443,6 +613,26 @@ impl<'def> Definitions<'def> {
     pub fn iter_mut(&mut self) -> IterMut<'_, (Cow<'def, str>, Definition<'def>)> {
         self.definitions.iter_mut()
     }
+
+    /// Merge a definition into the definition group with the given name
+    ///
+    /// Entries that already exist in the group take precedence. If the group doesn't exist it is
+    /// added.
+    pub fn merge(
+        mut self,
+        name: impl Into<Cow<'def, str>>,
+        definition: impl Into<Definition<'def>>,
+    ) -> Self {
+        let name = name.into();
+        match self.definitions.iter().position(|(n, _)| *n == name) {
+            Some(index) => {
+                let (_, existing) = &mut self.definitions[index];
+                *existing = std::mem::take(existing).merge(definition);
+            }
+            None => self.definitions.push((name, definition.into())),
+        }
+        self
+    }
 }
 
 impl<'def> IntoIterator for Definitions<'def> {
@@ -0,0 +1,586 @@
+use std::{borrow::Cow, path::Path, str::FromStr};
+
+use mlua_extras_grammar::{ParseResult, Ty};
+
+use crate::typed::{function::Return, Field, Func, Param, Type, TypedClassBuilder};
+
+use super::{Definition, Entry};
+
+/// Parses LuaLS type expressions, i.e. `fun(name: string): integer|nil`, with the grammar shared
+/// with the `lua_type!` macro
+pub(crate) struct TypeParser;
+
+impl TypeParser {
+    /// Parse a complete type expression
+    pub(crate) fn parse(src: &str) -> ParseResult<Type> {
+        mlua_extras_grammar::parse(src).map(into_type)
+    }
+
+    /// Parse a type expression from the start of the source returning the remaining text
+    pub(crate) fn parse_prefix(src: &str) -> ParseResult<(Type, &str)> {
+        mlua_extras_grammar::parse_prefix(src).map(|(ty, rest)| (into_type(ty), rest))
+    }
+}
+
+/// Build the [`Type`] a parsed expression describes
+fn into_type(ty: Ty) -> Type {
+    match ty {
+        Ty::Single(value) => Type::single(value),
+        Ty::Ref(name) => Type::reference(name),
+        Ty::LiteralString(value) => Type::literal_string(value),
+        Ty::Optional(inner) => into_type(*inner) | Type::single("nil"),
+        Ty::Union(types) => Type::Union(types.into_iter().map(into_type).collect()),
+        Ty::Array(inner) => Type::Array(Box::new(into_type(*inner))),
+        Ty::Map(key, value) => Type::Map(Box::new(into_type(*key)), Box::new(into_type(*value))),
+        Ty::Variadic(inner) => Type::Variadic(Box::new(into_type(*inner))),
+        Ty::Tuple(types) => Type::Tuple(types.into_iter().map(into_type).collect()),
+        Ty::Struct(fields) => Type::Struct(
+            fields
+                .into_iter()
+                .map(|(name, ty)| (Cow::Owned(name), into_type(ty)))
+                .collect(),
+        ),
+        Ty::Function {
+            params,
+            returns,
+            is_async,
+        } => Type::Function {
+            params: params
+                .into_iter()
+                .map(|(name, ty)| Param {
+                    doc: None,
+                    name: name.map(Cow::Owned),
+                    ty: into_type(ty),
+                })
+                .collect(),
+            returns: returns
+                .into_iter()
+                .map(|ty| Return {
+                    doc: None,
+                    ty: into_type(ty),
+                })
+                .collect(),
+            is_async,
+        },
+    }
+}
+
+/// Parses [LuaCATS](https://luals.github.io/wiki/annotations/) annotations from definition files
+/// back into a [`Definition`]
+///
+/// Supports `@class`, `@field`, `@param`, `@return`, `@alias`, `@type`, `@enum`, and `@async`.
+/// Other annotations are ignored. Functions are picked up from their declarations, i.e.
+/// `function Class:method(a, b) end`, using the `@param` and `@return` annotations above them.
+///
+/// # Example
+///
+/// ```
+/// use mlua_extras::typed::generator::{DefinitionParser, Definitions};
+///
+/// let definition = DefinitionParser::parse(r#"
+/// --- @meta
+///
+/// --- @class Player
+/// --- @field name string
+/// --- @field health integer
+///
+/// --- @param name string
+/// --- @return Player
+/// function spawn(name) end
+/// "#)?;
+///
+/// let definitions = Definitions::start().define("legacy", definition).finish();
+/// ```
+pub struct DefinitionParser;
+
+impl DefinitionParser {
+    /// Parse the annotations in lua source code
+    ///
+    /// Errors report the line that could not be parsed
+    pub fn parse(source: &str) -> mlua::Result<Definition<'static>> {
+        let mut state = State::default();
+        for (i, line) in source.lines().enumerate() {
+            state
+                .line(line.trim())
+                .map_err(|err| mlua::Error::runtime(format!("line {}: {err}", i + 1)))?;
+        }
+        state.close();
+        Ok(Definition {
+            entries: state.entries,
+            require: None,
+        })
+    }
+
+    /// Read and parse the annotations in a lua definition file
+    pub fn parse_file<P: AsRef<Path>>(path: P) -> mlua::Result<Definition<'static>> {
+        Self::parse(&std::fs::read_to_string(path).map_err(mlua::Error::external)?)
+    }
+
+    /// Parse a single LuaLS type expression. i.e. `string|nil`, `table<string, integer>`, `fun(a: number): boolean`
+    pub fn parse_type(ty: &str) -> mlua::Result<Type> {
+        TypeParser::parse(ty).map_err(mlua::Error::runtime)
+    }
+}
+
+/// Parse a LuaLS type expression
+///
+/// Use the `lua_type!` macro, with the `derive` feature, to check the
+/// expression at compile time.
+///
+/// # Example
+///
+/// ```
+/// use mlua_extras::typed::Type;
+///
+/// let ty: Type = "{ name: string, tags?: string[] }".parse()?;
+/// assert_eq!(ty.to_string(), "{ name: string, tags?: string[] }");
+/// ```
+impl FromStr for Type {
+    type Err = mlua::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        DefinitionParser::parse_type(s)
+    }
+}
+
+/// Parser state that is carried between lines
+#[derive(Default)]
+struct State {
+    entries: Vec<Entry<'static>>,
+
+    doc: Vec<String>,
+    params: Vec<Param>,
+    returns: Vec<Return>,
+    is_async: bool,
+    ty: Option<Type>,
+
+    /// Class entry that `@field` annotations are added to
+    class: Option<usize>,
+    /// Alias entry that `---| type` annotations are added to
+    alias: Option<usize>,
+    /// Name and doc of an `@enum` that is waiting for it's table
+    enumeration: Option<(String, Option<Cow<'static, str>>)>,
+    /// Enum entry that is collecting it's table's values
+    enum_values: Option<usize>,
+    /// Name of the class for a `local _Class_{name} = {` table and if inside it's `__metatable`
+    class_table: Option<(String, bool)>,
+}
+
+impl State {
+    fn line(&mut self, line: &str) -> ParseResult<()> {
+        if let Some(content) = line.strip_prefix("---") {
+            let content = content.trim();
+            if let Some(annotation) = content.strip_prefix('@') {
+                return self.annotation(annotation);
+            }
+            if let Some(variant) = content.strip_prefix('|') {
+                return self.alias_variant(variant);
+            }
+            if !content.is_empty() {
+                self.doc.push(content.to_string());
+            }
+            return Ok(());
+        }
+
+        if line.starts_with("--") {
+            return Ok(());
+        }
+
+        if let Some(index) = self.enum_values {
+            return self.enum_value(index, line);
+        }
+
+        let result = self.code(line);
+        self.close();
+        result
+    }
+
+    fn take_doc(&mut self) -> Option<Cow<'static, str>> {
+        if self.doc.is_empty() {
+            None
+        } else {
+            Some(std::mem::take(&mut self.doc).join("\n").into())
+        }
+    }
+
+    /// Close any open class or alias and clear annotations that were not used
+    fn close(&mut self) {
+        if let Some(index) = self.alias.take() {
+            if let Type::Alias(ty) = &mut self.entries[index].ty {
+                if let Type::Union(types) = ty.as_mut() {
+                    if types.len() == 1 {
+                        *ty.as_mut() = types.remove(0);
+                    }
+                }
+            }
+        }
+
+        self.class = None;
+        self.doc.clear();
+        self.params.clear();
+        self.returns.clear();
+        self.is_async = false;
+        self.ty = None;
+    }
+
+    fn annotation(&mut self, annotation: &str) -> ParseResult<()> {
+        let (tag, rest) = annotation
+            .split_once(char::is_whitespace)
+            .map(|(tag, rest)| (tag, rest.trim()))
+            .unwrap_or((annotation, ""));
+
+        match tag {
+            "class" => {
+                self.close_alias();
+                let name = skip_attribute(rest)
+                    .split(|c: char| c.is_whitespace() || c == ':')
+                    .next()
+                    .filter(|v| !v.is_empty())
+                    .ok_or("expected a class name")?;
+
+                let class = TypedClassBuilder {
+                    type_doc: self.take_doc(),
+                    ..Default::default()
+                };
+                self.entries
+                    .push(Entry::new(name.to_string(), Type::Class(Box::new(class))));
+                self.class = Some(self.entries.len() - 1);
+            }
+            "field" => {
+                let index = self.class.ok_or("@field must follow a @class")?;
+                let rest = match rest.split_once(char::is_whitespace) {
+                    Some(("public" | "private" | "protected" | "package", rest)) => rest.trim(),
+                    _ => rest,
+                };
+                let (name, optional, rest) = field_name(rest)?;
+                let (mut ty, rest) = TypeParser::parse_prefix(rest)?;
+                if optional {
+                    ty = ty | Type::single("nil");
+                }
+
+                let doc = join_doc(self.take_doc(), trailing_doc(rest));
+                if let Type::Class(class) = &mut self.entries[index].ty {
+                    // Index signatures, i.e. `[string] integer`, are keyed by their type
+                    match name.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+                        Some(key) => {
+                            class
+                                .index_fields
+                                .insert(TypeParser::parse(key)?, Field::new(ty, doc));
+                        }
+                        None => {
+                            class.fields.insert(name.into(), Field::new(ty, doc));
+                        }
+                    }
+                }
+            }
+            "param" => {
+                let (name, optional, rest) = field_name(rest)?;
+                let (mut ty, rest) = TypeParser::parse_prefix(rest)?;
+                if optional {
+                    ty = ty | Type::single("nil");
+                }
+                self.params.push(Param {
+                    doc: trailing_doc(rest),
+                    name: Some(name.into()),
+                    ty,
+                });
+            }
+            "return" => {
+                let (ty, rest) = TypeParser::parse_prefix(rest)?;
+                self.returns.push(Return {
+                    doc: trailing_doc(rest),
+                    ty,
+                });
+            }
+            "alias" => {
+                self.close_alias();
+                let (name, rest) = rest
+                    .split_once(char::is_whitespace)
+                    .map(|(name, rest)| (name, rest.trim()))
+                    .unwrap_or((rest, ""));
+                if name.is_empty() {
+                    return Err("expected an alias name".into());
+                }
+
+                let ty = if rest.is_empty() {
+                    Type::Union(Vec::new())
+                } else {
+                    TypeParser::parse_prefix(rest)?.0
+                };
+                let doc = self.take_doc();
+                self.entries
+                    .push(Entry::new_with(name.to_string(), Type::Alias(Box::new(ty)), doc));
+                self.alias = Some(self.entries.len() - 1);
+            }
+            "type" => {
+                self.ty = Some(TypeParser::parse_prefix(rest)?.0);
+            }
+            "enum" => {
+                let name = skip_attribute(rest)
+                    .split_whitespace()
+                    .next()
+                    .ok_or("expected an enum name")?;
+                let doc = self.take_doc();
+                self.enumeration = Some((name.to_string(), doc));
+            }
+            "async" => self.is_async = true,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn close_alias(&mut self) {
+        if self.alias.is_some() {
+            let doc = std::mem::take(&mut self.doc);
+            self.close();
+            self.doc = doc;
+        }
+    }
+
+    /// Additional alias variant. i.e. `---| "value" # doc`
+    fn alias_variant(&mut self, variant: &str) -> ParseResult<()> {
+        let Some(index) = self.alias else {
+            return Ok(());
+        };
+
+        let (variant, _) = TypeParser::parse_prefix(variant)?;
+        if let Type::Alias(ty) = &mut self.entries[index].ty {
+            *ty.as_mut() = std::mem::replace(ty.as_mut(), Type::Union(Vec::new())) | variant;
+        }
+        Ok(())
+    }
+
+    /// A `Key = value,` line inside of an `@enum` table
+    fn enum_value(&mut self, index: usize, line: &str) -> ParseResult<()> {
+        if line.starts_with('}') {
+            self.enum_values = None;
+            return Ok(());
+        }
+
+        if let Type::Enum(_, types) = &mut self.entries[index].ty {
+            types.extend(enum_values(line));
+        }
+        Ok(())
+    }
+
+    fn code(&mut self, line: &str) -> ParseResult<()> {
+        if line.is_empty() {
+            return Ok(());
+        }
+
+        if let Some((name, doc)) = self.enumeration.take() {
+            if let Some((_, body)) = line.split_once('{') {
+                self.entries
+                    .push(Entry::new_with(name.clone(), Type::Enum(name.into(), Vec::new()), doc));
+                let index = self.entries.len() - 1;
+                match body.split_once('}') {
+                    Some((body, _)) => {
+                        if let Type::Enum(_, types) = &mut self.entries[index].ty {
+                            types.extend(enum_values(body));
+                        }
+                    }
+                    None => self.enum_values = Some(index),
+                }
+                return Ok(());
+            }
+        }
+
+        if let Some((class, meta)) = self.class_table.clone() {
+            if line == "__metatable = {" {
+                self.class_table = Some((class, true));
+            } else if line.starts_with('}') {
+                self.class_table = if meta { Some((class, false)) } else { None };
+            } else if let Some((name, args)) = assigned_function(line) {
+                self.function(Some(&class), name, args, false, meta);
+            }
+            return Ok(());
+        }
+
+        if let Some(class) = line
+            .strip_prefix("local _Class_")
+            .and_then(|rest| rest.strip_suffix('{'))
+            .and_then(|rest| rest.trim().strip_suffix('='))
+        {
+            self.class_table = Some((class.trim().to_string(), false));
+            return Ok(());
+        }
+
+        let (is_local, line) = match line.strip_prefix("local ") {
+            Some(rest) => (true, rest.trim()),
+            None => (false, line),
+        };
+
+        let declaration = line
+            .strip_prefix("function ")
+            .and_then(|rest| rest.split_once('('))
+            .and_then(|(name, rest)| Some((name.trim(), rest.split_once(')')?.0)))
+            .or_else(|| assigned_function(line));
+
+        if let Some((name, args)) = declaration {
+            let (owner, name, method) = match name.rsplit_once(':') {
+                Some((owner, name)) => (Some(owner), name, true),
+                None => match name.rsplit_once('.') {
+                    Some((owner, name)) => (Some(owner), name, false),
+                    None => (None, name, false),
+                },
+            };
+
+            if self.find_class(owner).is_some() {
+                self.function(owner, name, args, method, false);
+            } else if !is_local {
+                let full = match owner {
+                    Some(owner) => format!("{owner}{}{name}", if method { ":" } else { "." }),
+                    None => name.to_string(),
+                };
+                let func = self.func(args, method);
+                self.entries.push(Entry::new_with(
+                    full,
+                    Type::Function {
+                        params: func.params,
+                        returns: func.returns,
+                        is_async: func.is_async,
+                    },
+                    func.doc,
+                ));
+            }
+            return Ok(());
+        }
+
+        if let Some(ty) = self.ty.take() {
+            if !is_local {
+                if let Some((name, _)) = line.split_once('=') {
+                    let doc = self.take_doc();
+                    self.entries.push(Entry::new_with(
+                        name.trim().to_string(),
+                        Type::Value(Box::new(ty)),
+                        doc,
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn find_class(&self, name: Option<&str>) -> Option<usize> {
+        let name = name?;
+        self.entries
+            .iter()
+            .position(|entry| entry.name == name && matches!(entry.ty, Type::Class(_)))
+    }
+
+    /// Build a function from it's declared args and the collected annotations
+    fn func(&mut self, args: &str, method: bool) -> Func {
+        let mut args = args
+            .split(',')
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .collect::<Vec<_>>();
+        if method && args.first() == Some(&"self") {
+            args.remove(0);
+        }
+
+        let params = args
+            .into_iter()
+            .map(|arg| {
+                self.params
+                    .iter()
+                    .find(|param| param.name.as_deref() == Some(arg))
+                    .cloned()
+                    .unwrap_or_else(|| Param {
+                        doc: None,
+                        name: Some(arg.to_string().into()),
+                        ty: Type::single("any"),
+                    })
+            })
+            .collect();
+
+        Func {
+            params,
+            returns: std::mem::take(&mut self.returns),
+            doc: self.take_doc(),
+            is_async: self.is_async,
+        }
+    }
+
+    /// Add a function or method to a class
+    fn function(&mut self, class: Option<&str>, name: &str, args: &str, method: bool, meta: bool) {
+        let Some(index) = self.find_class(class) else {
+            return;
+        };
+
+        let method = method || args.split(',').next().map(str::trim) == Some("self");
+        let func = self.func(args, true);
+        if let Type::Class(class) = &mut self.entries[index].ty {
+            let functions = match (method, meta) {
+                (true, true) => &mut class.meta_methods,
+                (true, false) => &mut class.methods,
+                (false, true) => &mut class.meta_functions,
+                (false, false) => &mut class.functions,
+            };
+            functions.insert(name.to_string().into(), func);
+        }
+        self.doc.clear();
+        self.params.clear();
+        self.is_async = false;
+    }
+}
+
+/// Skip a parenthesized attribute. i.e. `(exact)` in `@class (exact) Name`
+fn skip_attribute(value: &str) -> &str {
+    match value.strip_prefix('(').and_then(|rest| rest.split_once(')')) {
+        Some((_, rest)) => rest.trim(),
+        None => value,
+    }
+}
+
+/// Name of a field or param returning the name, if it is optional, and the remaining text
+///
+/// Index signatures, i.e. `[string]`, are kept as the name
+fn field_name(value: &str) -> ParseResult<(String, bool, &str)> {
+    let (name, rest) = if value.starts_with('[') {
+        let end = value.find(']').ok_or("unterminated index signature")?;
+        value.split_at(end + 1)
+    } else {
+        value
+            .split_once(char::is_whitespace)
+            .ok_or_else(|| format!("expected a name and type: `{value}`"))?
+    };
+
+    Ok(match name.strip_suffix('?') {
+        Some(name) => (name.to_string(), true, rest.trim()),
+        None => (name.to_string(), false, rest.trim()),
+    })
+}
+
+/// Doc comment after a type. The optional leading `#` is removed
+fn trailing_doc(value: &str) -> Option<Cow<'static, str>> {
+    let value = value.trim();
+    let value = value.strip_prefix('#').unwrap_or(value).trim();
+    (!value.is_empty()).then(|| value.to_string().into())
+}
+
+fn join_doc(
+    first: Option<Cow<'static, str>>,
+    second: Option<Cow<'static, str>>,
+) -> Option<Cow<'static, str>> {
+    match (first, second) {
+        (Some(first), Some(second)) => Some(format!("{first}\n{second}").into()),
+        (first, second) => first.or(second),
+    }
+}
+
+/// `name = function(args) end` returning the name and args
+fn assigned_function(line: &str) -> Option<(&str, &str)> {
+    let (name, rest) = line.split_once('=')?;
+    let args = rest.trim().strip_prefix("function")?.trim().strip_prefix('(')?;
+    Some((name.trim(), args.split_once(')')?.0))
+}
+
+/// Values of `Key = value` pairs in an enum table
+fn enum_values(body: &str) -> impl Iterator<Item = Type> + '_ {
+    body.split([',', ';'])
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(_, value)| value.trim())
+        .filter(|value| !value.is_empty())
+        .map(|value| Type::single(value.to_string()))
+}
@@ -0,0 +1,195 @@
+use std::collections::{BTreeSet, HashSet};
+
+use mlua::{AnyUserData, Lua, MultiValue, Table, Value};
+
+use crate::typed::{Field, Func, Type, TypedModuleBuilder, TypedMultiValue};
+
+use super::{Definition, Entry};
+
+/// Globals and `package.loaded` entries that are part of the lua standard library
+const STD_GLOBALS: &[&str] = &[
+    "_G", "_VERSION", "_ENV", "assert", "bit32", "collectgarbage", "coroutine", "debug", "dofile",
+    "error", "gcinfo", "getfenv", "getmetatable", "io", "ipairs", "jit", "load", "loadfile",
+    "loadstring", "math", "module", "newproxy", "next", "os", "package", "pairs", "pcall", "print",
+    "rawequal", "rawget", "rawlen", "rawset", "require", "select", "setfenv", "setmetatable",
+    "string", "table", "tonumber", "tostring", "type", "unpack", "utf8", "xpcall",
+];
+
+/// Introspect values that are already in a lua instance and build basic definitions for them
+///
+/// This is useful for apis that are registered without the typed traits, i.e. with
+/// [`create_function`][mlua::Lua::create_function] or from third party C modules.
+///
+/// - Tables are recorded as modules
+/// - Functions are recorded as `fun(...: any): ...any`
+/// - Userdata is recorded as a reference to it's metatable's `__name`
+/// - Other values are recorded by their lua type
+///
+/// The lua standard library is ignored by default. Merge the scanned definition into typed
+/// definitions with [`Definition::merge`] so typed registrations take precedence.
+///
+/// # Example
+///
+/// ```
+/// use mlua_extras::{mlua::Lua, typed::generator::{Definition, Scanner}};
+///
+/// let lua = Lua::new();
+/// lua.globals().set("greet", lua.create_function(|_, name: String| Ok(format!("Hello, {name}!")))?)?;
+///
+/// let definition = Definition::start()
+///     .value::<String>("version")
+///     .finish()
+///     .merge(Scanner::new().scan_globals(&lua)?);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Scanner {
+    ignore: BTreeSet<String>,
+    depth: usize,
+}
+
+impl Default for Scanner {
+    fn default() -> Self {
+        Self {
+            ignore: STD_GLOBALS.iter().map(|v| v.to_string()).collect(),
+            depth: 8,
+        }
+    }
+}
+
+impl Scanner {
+    /// Create a new scanner that ignores the lua standard library
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ignore a root level name while scanning
+    pub fn ignore(mut self, name: impl Into<String>) -> Self {
+        self.ignore.insert(name.into());
+        self
+    }
+
+    /// Set how many nested tables deep to scan. Tables past this depth are recorded as `table`
+    pub fn depth(mut self, depth: usize) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    /// Scan all values in `_G`
+    pub fn scan_globals(&self, lua: &Lua) -> mlua::Result<Definition<'static>> {
+        self.scan_table(&lua.globals())
+    }
+
+    /// Scan all modules that have been loaded with `require`, i.e. `package.loaded`
+    pub fn scan_loaded(&self, lua: &Lua) -> mlua::Result<Definition<'static>> {
+        let package = lua.globals().get::<Table>("package")?;
+        self.scan_table(&package.get::<Table>("loaded")?)
+    }
+
+    /// Scan all values in a table, recording each key as a root level definition entry
+    pub fn scan_table(&self, table: &Table) -> mlua::Result<Definition<'static>> {
+        let mut visited = HashSet::from([table.to_pointer()]);
+        let mut entries = Vec::new();
+
+        for pair in table.pairs::<Value, Value>() {
+            let (key, value) = pair?;
+            let Some(name) = Self::key_name(&key) else {
+                continue;
+            };
+            if self.ignore.contains(&name) {
+                continue;
+            }
+
+            let ty = match value {
+                Value::Nil | Value::Error(_) => continue,
+                Value::Table(table) => match self.scan_module(&table, &mut visited, 1)? {
+                    Some(module) => Type::module(module),
+                    None => Type::Value(Box::new(Type::single("table"))),
+                },
+                Value::Function(_) => Type::function::<MultiValue, MultiValue>(),
+                other => Type::Value(Box::new(Self::value_type(&other)?)),
+            };
+            entries.push(Entry::new(name, ty));
+        }
+
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(Definition {
+            entries,
+            require: None,
+        })
+    }
+
+    fn scan_module(
+        &self,
+        table: &Table,
+        visited: &mut HashSet<*const std::ffi::c_void>,
+        depth: usize,
+    ) -> mlua::Result<Option<TypedModuleBuilder>> {
+        if depth > self.depth || !visited.insert(table.to_pointer()) {
+            return Ok(None);
+        }
+
+        let mut module = TypedModuleBuilder::default();
+        for pair in table.pairs::<Value, Value>() {
+            let (key, value) = pair?;
+            let Some(name) = Self::key_name(&key) else {
+                continue;
+            };
+
+            match value {
+                Value::Nil | Value::Error(_) => {}
+                Value::Table(table) => match self.scan_module(&table, visited, depth + 1)? {
+                    Some(nested) => {
+                        module.nested_modules.insert(name.into(), nested);
+                    }
+                    None => {
+                        module
+                            .fields
+                            .insert(name.into(), Field::new(Type::single("table"), None));
+                    }
+                },
+                Value::Function(_) => {
+                    module.functions.insert(
+                        name.into(),
+                        Func {
+                            params: MultiValue::get_types_as_params(),
+                            returns: MultiValue::get_types_as_returns(),
+                            doc: None,
+                            is_async: false,
+                        },
+                    );
+                }
+                other => {
+                    module
+                        .fields
+                        .insert(name.into(), Field::new(Self::value_type(&other)?, None));
+                }
+            }
+        }
+        Ok(Some(module))
+    }
+
+    /// Only string keys can be represented as named definitions
+    fn key_name(key: &Value) -> Option<String> {
+        match key {
+            Value::String(name) => name.to_str().ok().map(|v| v.to_string()),
+            _ => None,
+        }
+    }
+
+    fn value_type(value: &Value) -> mlua::Result<Type> {
+        Ok(match value {
+            Value::UserData(data) => Self::userdata_name(data)?
+                .map(Type::reference)
+                .unwrap_or_else(|| Type::single("userdata")),
+            other => Type::single(other.type_name()),
+        })
+    }
+
+    /// Get the userdata's class name from the `__name` field in it's metatable
+    fn userdata_name(data: &AnyUserData) -> mlua::Result<Option<String>> {
+        match data.metatable() {
+            Ok(meta) => meta.get::<Option<String>>("__name"),
+            Err(_) => Ok(None),
+        }
+    }
+}
@@ -1,6 +1,11 @@
-use std::{borrow::Cow, path::Path, slice::Iter};
+use std::{
+    borrow::Cow,
+    collections::HashSet,
+    path::Path,
+    slice::Iter,
+};
 
-use crate::typed::{function::Return, Param, Type, TypedModuleBuilder};
+use crate::typed::{function::Return, Field, Param, Type, TypedModuleBuilder};
 
 use super::{Definition, Definitions};
 
@@ -57,10 +62,14 @@ impl<'def> DefinitionFileGenerator<'def> {
         self
     }
 
+    /// Iterate the file name and writer for each definition group
+    ///
+    /// Classes, enums, and aliases are only written in the first file that defines them
     pub fn iter(&self) -> DefinitionFileIter<'_> {
         DefinitionFileIter {
             extension: self.extension.clone(),
             definitions: self.definitions.iter(),
+            written: HashSet::new(),
         }
     }
 }
@@ -68,6 +77,8 @@ impl<'def> DefinitionFileGenerator<'def> {
 pub struct DefinitionFileIter<'def> {
     extension: String,
     definitions: Iter<'def, (Cow<'def, str>, Definition<'def>)>,
+    /// Named types that have already been written to a file
+    written: HashSet<&'def str>,
 }
 
 impl<'def> Iterator for DefinitionFileIter<'def> {
@@ -75,9 +86,24 @@ impl<'def> Iterator for DefinitionFileIter<'def> {
 
     fn next(&mut self) -> Option<Self::Item> {
         self.definitions.next().map(|v| {
+            let skip = v
+                .1
+                .entries
+                .iter()
+                .enumerate()
+                .filter(|(_, entry)| {
+                    matches!(entry.ty, Type::Class(_) | Type::Enum(_, _) | Type::Alias(_) | Type::Struct(_))
+                        && !self.written.insert(entry.name.as_ref())
+                })
+                .map(|(i, _)| i)
+                .collect();
+
             (
                 format!("{}{}", v.0, self.extension),
-                DefinitionWriter { definition: &v.1 },
+                DefinitionWriter {
+                    definition: &v.1,
+                    skip,
+                },
             )
         })
     }
@@ -85,6 +111,8 @@ impl<'def> Iterator for DefinitionFileIter<'def> {
 
 pub struct DefinitionWriter<'def> {
     definition: &'def Definition<'def>,
+    /// Index of entries that are already written in another file
+    skip: HashSet<usize>,
 }
 
 impl DefinitionWriter<'_> {
@@ -102,9 +130,19 @@ impl DefinitionWriter<'_> {
     ///
     /// Write the full definition group to the specified `io`
     pub fn write<W: std::io::Write>(&self, mut buffer: W) -> mlua::Result<()> {
-        writeln!(buffer, "--- @meta\n")?;
+        let require = self.definition.require.as_deref();
+        match require {
+            Some(name) => writeln!(buffer, "--- @meta {name}\n")?,
+            None => writeln!(buffer, "--- @meta\n")?,
+        }
+
+        // A required module is returned from the file instead of being a global
+        let mut returns = None;
+        for (i, definition) in self.definition.iter().enumerate() {
+            if self.skip.contains(&i) {
+                continue;
+            }
 
-        for definition in self.definition.iter() {
             match &definition.ty {
                 Type::Value(ty) => {
                     if let Some(docs) = Self::accumulate_docs(&[definition.doc.as_deref()]) {
@@ -126,22 +164,21 @@ impl DefinitionWriter<'_> {
                         if let Some(docs) = Self::accumulate_docs(&[field.doc.as_deref()]) {
                             writeln!(buffer, "{}", docs.join("\n"))?;
                         }
-                        writeln!(
-                            buffer,
-                            "--- @field {name} {}",
-                            Self::type_signature(&field.ty)?
-                        )?;
+                        writeln!(buffer, "{}", Self::field_signature(name, field)?)?;
                     }
 
                     for (name, field) in type_data.fields.iter() {
                         if let Some(docs) = Self::accumulate_docs(&[field.doc.as_deref()]) {
                             writeln!(buffer, "{}", docs.join("\n"))?;
                         }
-                        writeln!(
-                            buffer,
-                            "--- @field {name} {}",
-                            Self::type_signature(&field.ty)?
-                        )?;
+                        writeln!(buffer, "{}", Self::field_signature(name, field)?)?;
+                    }
+
+                    for (key, field) in type_data.index_fields.iter() {
+                        if let Some(docs) = Self::accumulate_docs(&[field.doc.as_deref()]) {
+                            writeln!(buffer, "{}", docs.join("\n"))?;
+                        }
+                        writeln!(buffer, "{}", Self::index_signature(key, field)?)?;
                     }
 
                     if !type_data.functions.is_empty()
@@ -163,6 +200,7 @@ impl DefinitionWriter<'_> {
                                     name.to_string(),
                                     &func.params,
                                     &func.returns,
+                                    func.is_async,
                                     true
                                 )?
                                 .join("\n  ")
@@ -181,6 +219,7 @@ impl DefinitionWriter<'_> {
                                     definition.name.to_string(),
                                     &func.params,
                                     &func.returns,
+                                    func.is_async,
                                     true
                                 )?
                                 .join("\n  ")
@@ -211,6 +250,7 @@ impl DefinitionWriter<'_> {
                                         name.to_string(),
                                         &func.params,
                                         &func.returns,
+                                        func.is_async,
                                         true
                                     )?
                                     .join("\n    ")
@@ -229,6 +269,7 @@ impl DefinitionWriter<'_> {
                                         definition.name.to_string(),
                                         &func.params,
                                         &func.returns,
+                                        func.is_async,
                                         true
                                     )?
                                     .join("\n    ")
@@ -265,7 +306,7 @@ impl DefinitionWriter<'_> {
                         Self::type_signature(ty)?
                     )?;
                 }
-                Type::Function { params, returns } => {
+                Type::Function { params, returns, is_async } => {
                     if let Some(docs) = Self::accumulate_docs(&[definition.doc.as_deref()]) {
                         writeln!(buffer, "{}", docs.join("\n"))?;
                     }
@@ -276,6 +317,7 @@ impl DefinitionWriter<'_> {
                             definition.name.to_string(),
                             params,
                             returns,
+                            *is_async,
                             false
                         )?
                         .join("\n")
@@ -288,11 +330,33 @@ impl DefinitionWriter<'_> {
                         writeln!(buffer, "{}", docs.join("\n"))?;
                     }
 
-                    write!(buffer, "{} = ", definition.name)?;
+                    Self::write_module_class(&mut buffer, module, &definition.name, "")?;
+                    if require == Some(definition.name.as_ref()) {
+                        let local = local_name(
+                            definition
+                                .name
+                                .rsplit_once('.')
+                                .map(|v| v.1)
+                                .unwrap_or(&definition.name),
+                        );
+                        write!(buffer, "local {local} = ")?;
+                        returns = Some(local);
+                    } else {
+                        write!(buffer, "{} = ", definition.name)?;
+                    }
                     let mut path = Vec::new();
-                    Self::write_module(&mut buffer, module, &mut path)?;
+                    Self::write_module(&mut buffer, module, &definition.name, &mut path)?;
                     writeln!(buffer)?;
                 },
+                Type::Struct(fields) => {
+                    if let Some(docs) = Self::accumulate_docs(&[definition.doc.as_deref()]) {
+                        writeln!(buffer, "{}", docs.join("\n"))?;
+                    }
+                    writeln!(buffer, "--- @class {}", definition.name)?;
+                    for (name, ty) in fields.iter() {
+                        writeln!(buffer, "{}", Self::field_signature(name, &Field::new(ty.clone(), None))?)?;
+                    }
+                },
                 other => {
                     return Err(mlua::Error::runtime(format!(
                         "invalid root level type: {}",
@@ -303,6 +367,10 @@ impl DefinitionWriter<'_> {
             writeln!(buffer)?;
         }
 
+        if let Some(local) = returns {
+            writeln!(buffer, "return {local}")?;
+        }
+
         Ok(())
     }
 
@@ -310,9 +378,13 @@ impl DefinitionWriter<'_> {
         name: String,
         params: &[Param],
         returns: &[Return],
+        is_async: bool,
         assign: bool,
     ) -> mlua::Result<Vec<String>> {
         let mut result = Vec::new();
+        if is_async {
+            result.push("--- @async".to_string());
+        }
 
         for (i, param) in params.iter().enumerate() {
             let doc = param.doc.as_deref().unwrap_or_default();
@@ -358,9 +430,15 @@ impl DefinitionWriter<'_> {
         class: String,
         params: &[Param],
         returns: &[Return],
+        is_async: bool,
         assign: bool,
     ) -> mlua::Result<Vec<String>> {
-        let mut result = Vec::from([format!("--- @param self {class}")]);
+        let mut result = Vec::new();
+        if is_async {
+            result.push("--- @async".to_string());
+        }
+        result.push(format!("--- @param self {class}"));
+
         for (i, param) in params.iter().enumerate() {
             let doc = param.doc.as_deref().unwrap_or_default();
             result.push(match param.name.as_deref() {
@@ -401,84 +479,20 @@ impl DefinitionWriter<'_> {
         Ok(result)
     }
 
-    fn type_signature(ty: &Type) -> mlua::Result<String> {
-        Ok(match ty {
-            Type::Enum(name, _) => name.to_string(),
-            Type::Single(value) => value.to_string(),
-            Type::Tuple(types) => {
-                format!(
-                    "{{ {} }}",
-                    types
-                        .iter()
-                        .enumerate()
-                        .map(|(i, t)| Ok(format!("[{}]: {}", i + 1, Self::type_signature(t)?)))
-                        .collect::<mlua::Result<Vec<_>>>()?
-                        .join(", ")
-                )
-            }
-            Type::Variadic(ty) => {
-                format!("...{}", Self::type_signature(ty)?)
-            }
-            Type::Array(ty) => {
-                format!("{{ [integer]: {} }}", Self::type_signature(ty)?)
-            }
-            Type::Map(key, value) => {
-                format!(
-                    "{{ [{}]: {} }}",
-                    Self::type_signature(key)?,
-                    Self::type_signature(value)?
-                )
-            }
-            Type::Function { params, returns } => {
-                format!(
-                    "fun({}){}",
-                    params
-                        .iter()
-                        .enumerate()
-                        .map(|(i, v)| {
-                            v.name
-                                .as_ref()
-                                .map(|v| v.to_string())
-                                .unwrap_or(format!("param{i}"))
-                        })
-                        .collect::<Vec<_>>()
-                        .join(", "),
-                    if returns.is_empty() {
-                        String::new()
-                    } else {
-                        format!(
-                            ": {}",
-                            returns
-                                .iter()
-                                .map(|v| Self::type_signature(&v.ty))
-                                .collect::<mlua::Result<Vec<_>>>()?
-                                .join(", ")
-                        )
-                    }
-                )
-            }
-            Type::Union(types) => types
-                .iter()
-                .map(Self::type_signature)
-                .collect::<mlua::Result<Vec<_>>>()?
-                .join(" | "),
-            Type::Struct(entries) => {
-                format!(
-                    "{{ {} }}",
-                    entries
-                        .iter()
-                        .map(|(k, v)| { Ok(format!("{k}: {}", Self::type_signature(v)?)) })
-                        .collect::<mlua::Result<Vec<_>>>()?
-                        .join(", ")
-                )
-            }
-            other => {
-                return Err(mlua::Error::runtime(format!(
+    /// Type signature written with the [`Display`][std::fmt::Display] impl of [`Type`]
+    ///
+    /// Classes, modules, values, and aliases are entries rather than types so they can't be a
+    /// type signature on their own.
+    pub(crate) fn type_signature(ty: &Type) -> mlua::Result<String> {
+        match ty {
+            Type::Class(_) | Type::Module(_) | Type::Value(_) | Type::Alias(_) => {
+                Err(mlua::Error::runtime(format!(
                     "type cannot be a type signature: {}",
-                    other.as_ref()
+                    ty.as_ref()
                 )))
             }
-        })
+            other => Ok(other.to_string()),
+        }
     }
 
     fn accumulate_docs(docs: &[Option<&str>]) -> Option<Vec<String>> {
@@ -490,7 +504,43 @@ impl DefinitionWriter<'_> {
         })
     }
 
-    fn write_module<B: std::io::Write>(buffer: &mut B, module: &TypedModuleBuilder, path: &mut Vec<String>) -> mlua::Result<()> {
+    /// Class field annotation. Optional fields are written as `--- @field name? <type>`
+    fn field_signature(name: &str, field: &Field) -> mlua::Result<String> {
+        Ok(if field.is_optional() {
+            format!("--- @field {name}? {}", Self::type_signature(&field.ty.without_nil())?)
+        } else {
+            format!("--- @field {name} {}", Self::type_signature(&field.ty)?)
+        })
+    }
+
+    /// Index signature annotation. i.e. `--- @field [string] integer`
+    fn index_signature(key: &Type, field: &Field) -> mlua::Result<String> {
+        Ok(format!(
+            "--- @field [{}] {}",
+            Self::type_signature(key)?,
+            Self::type_signature(&field.ty)?
+        ))
+    }
+
+    /// Modules with index signatures are annotated as a class so the signatures can be written
+    ///
+    /// The module's docs are expected to already be written
+    fn write_module_class<B: std::io::Write>(buffer: &mut B, module: &TypedModuleBuilder, name: &str, offset: &str) -> mlua::Result<()> {
+        if module.index_fields.is_empty() {
+            return Ok(());
+        }
+
+        writeln!(buffer, "{offset}--- @class {name}")?;
+        for (key, field) in module.index_fields.iter() {
+            if let Some(docs) = Self::accumulate_docs(&[field.doc.as_deref()]) {
+                writeln!(buffer, "{offset}{}", docs.join(format!("\n{offset}").as_str()))?;
+            }
+            writeln!(buffer, "{offset}{}", Self::index_signature(key, field)?)?;
+        }
+        Ok(())
+    }
+
+    fn write_module<B: std::io::Write>(buffer: &mut B, module: &TypedModuleBuilder, module_name: &str, path: &mut Vec<String>) -> mlua::Result<()> {
         let indent = path.len()*2;
         let current_offset = (0..indent).map(|_| ' ').collect::<String>();
         let single_offset = (0..indent+2).map(|_| ' ').collect::<String>();
@@ -509,9 +559,11 @@ impl DefinitionWriter<'_> {
 
             match &field.ty {
                 &Type::Module(ref module) => {
+                    let module_name = format!("{module_name}.{name}");
+                    Self::write_module_class(buffer, module, &module_name, &single_offset)?;
                     write!(buffer, "{single_offset}{name} = ")?;
                     path.push(name.to_string());
-                    Self::write_module(buffer, module, path)?;
+                    Self::write_module(buffer, module, &module_name, path)?;
                     path.pop();
                     writeln!(buffer, ",")?;
                 },
@@ -527,9 +579,11 @@ impl DefinitionWriter<'_> {
                 writeln!(buffer, "{single_offset}{}", docs.join(format!("\n{single_offset}").as_str()))?;
             }
 
+            let module_name = format!("{module_name}.{name}");
+            Self::write_module_class(buffer, nested, &module_name, &single_offset)?;
             write!(buffer, "{single_offset}{name} = ")?;
             path.push(name.to_string());
-            Self::write_module(buffer, nested, path)?;
+            Self::write_module(buffer, nested, &module_name, path)?;
             path.pop();
             writeln!(buffer, ",")?;
         }
@@ -539,7 +593,7 @@ impl DefinitionWriter<'_> {
                 writeln!(buffer, "{single_offset}{}", docs.join(format!("\n{single_offset}").as_str()))?;
             }
 
-            writeln!(buffer, "{single_offset}{},", Self::function_signature(name.to_string(), &func.params, &func.returns, true)?.join(format!("\n{single_offset}").as_str()))?;
+            writeln!(buffer, "{single_offset}{},", Self::function_signature(name.to_string(), &func.params, &func.returns, func.is_async, true)?.join(format!("\n{single_offset}").as_str()))?;
         }
 
         for (name, func) in module.methods.iter() {
@@ -547,7 +601,7 @@ impl DefinitionWriter<'_> {
                 writeln!(buffer, "{single_offset}{}", docs.join(format!("\n{single_offset}").as_str()))?;
             }
 
-            writeln!(buffer, "{single_offset}{},", Self::method_signature(name.to_string(), "table".into(), &func.params, &func.returns, true)?.join(format!("\n{single_offset}").as_str()))?;
+            writeln!(buffer, "{single_offset}{},", Self::method_signature(name.to_string(), "table".into(), &func.params, &func.returns, func.is_async, true)?.join(format!("\n{single_offset}").as_str()))?;
         }
 
         if !module.is_meta_empty() {
@@ -562,9 +616,11 @@ impl DefinitionWriter<'_> {
 
                 match &field.ty {
                     &Type::Module(ref module) => {
+                        let module_name = format!("{module_name}.__metatable.{name}");
+                        Self::write_module_class(buffer, module, &module_name, &double_offset)?;
                         write!(buffer, "{double_offset}{name} = ")?;
                         path.push(name.to_string());
-                        Self::write_module(buffer, module, path)?;
+                        Self::write_module(buffer, module, &module_name, path)?;
                         path.pop();
                         writeln!(buffer, ",")?;
                     },
@@ -580,7 +636,7 @@ impl DefinitionWriter<'_> {
                     writeln!(buffer, "{double_offset}{}", docs.join(format!("\n{double_offset}").as_str()))?;
                 }
 
-                writeln!(buffer, "{double_offset}{},", Self::function_signature(name.to_string(), &func.params, &func.returns, true)?.join(format!("\n{double_offset}").as_str()))?;
+                writeln!(buffer, "{double_offset}{},", Self::function_signature(name.to_string(), &func.params, &func.returns, func.is_async, true)?.join(format!("\n{double_offset}").as_str()))?;
             }
 
             for (name, func) in module.meta_methods.iter() {
@@ -588,7 +644,7 @@ impl DefinitionWriter<'_> {
                     writeln!(buffer, "{double_offset}{}", docs.join(format!("\n{double_offset}").as_str()))?;
                 }
 
-                writeln!(buffer, "{double_offset}{},", Self::method_signature(name.to_string(), "table".into(), &func.params, &func.returns, true)?.join(format!("\n{double_offset}").as_str()))?;
+                writeln!(buffer, "{double_offset}{},", Self::method_signature(name.to_string(), "table".into(), &func.params, &func.returns, func.is_async, true)?.join(format!("\n{double_offset}").as_str()))?;
             }
 
             writeln!(buffer, "{single_offset}}},")?;
@@ -599,3 +655,26 @@ impl DefinitionWriter<'_> {
         Ok(())
     }
 }
+
+/// Turn a module name into a valid lua identifier for it's local variable
+///
+/// Invalid characters are replaced with `_`, names starting with a digit are prefixed with `_`,
+/// and keywords are suffixed with `_`. i.e. `my-mod` is `my_mod` and `end` is `end_`
+fn local_name(name: &str) -> String {
+    const KEYWORDS: &[&str] = &[
+        "and", "break", "do", "else", "elseif", "end", "false", "for", "function", "goto", "if",
+        "in", "local", "nil", "not", "or", "repeat", "return", "then", "true", "until", "while",
+    ];
+
+    let mut local = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' })
+        .collect::<String>();
+    if local.is_empty() || local.starts_with(|c: char| c.is_ascii_digit()) {
+        local.insert(0, '_');
+    }
+    if KEYWORDS.contains(&local.as_str()) {
+        local.push('_');
+    }
+    local
+}
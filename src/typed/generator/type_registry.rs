@@ -0,0 +1,282 @@
+use std::collections::BTreeMap;
+
+use crate::typed::{Field, Func, Type, TypedClassBuilder, TypedModuleBuilder};
+
+use super::Definitions;
+
+/// A problem found while resolving the named types in a collection of definitions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypeIssue {
+    /// A referenced type is not defined in any definition
+    Undefined {
+        /// Name of the referenced type
+        name: String,
+        /// Where the reference was found. i.e. `init: Player.parent`
+        path: String,
+    },
+    /// Different types are defined with the same name
+    Duplicate {
+        /// Name of the type
+        name: String,
+        /// Definition groups that define the name
+        groups: Vec<String>,
+    },
+}
+
+impl std::fmt::Display for TypeIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Undefined { name, path } => write!(f, "undefined type '{name}' used in {path}"),
+            Self::Duplicate { name, groups } => write!(
+                f,
+                "type '{name}' is defined differently in: {}",
+                groups.join(", ")
+            ),
+        }
+    }
+}
+
+/// Named types, i.e. classes, aliases, and enums, collected from all groups in [`Definitions`]
+///
+/// Resolves [`Type::Ref`] to the type it names and reports references to types that are never
+/// defined along with names that are defined differently in multiple places. The same class
+/// registered in multiple groups is not a duplicate, the generator only writes it once.
+///
+/// # Example
+///
+/// ```
+/// use mlua_extras::typed::{generator::{Definition, Definitions, TypeRegistry}, Type};
+///
+/// let definitions = Definitions::start()
+///     .define("init", Definition::start()
+///         .alias("Id", Type::single("integer"))
+///         .alias("Ids", Type::Array(Box::new(Type::reference("Id"))))
+///     )
+///     .finish();
+///
+/// let registry = TypeRegistry::new(&definitions);
+/// assert!(registry.resolve(&Type::reference("Id")).is_some());
+/// registry.check()?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct TypeRegistry<'a> {
+    types: BTreeMap<&'a str, (&'a str, &'a Type)>,
+    issues: Vec<TypeIssue>,
+}
+
+impl<'a> TypeRegistry<'a> {
+    /// Collect the named types from all definition groups and check their references
+    pub fn new<'def: 'a>(definitions: &'a Definitions<'def>) -> Self {
+        let mut types = BTreeMap::<&'a str, (&'a str, &'a Type)>::new();
+        let mut duplicates = BTreeMap::<&'a str, Vec<String>>::new();
+
+        for (group, definition) in definitions.iter() {
+            for entry in definition.entries.iter() {
+                if !matches!(
+                    entry.ty,
+                    Type::Class(_) | Type::Enum(_, _) | Type::Alias(_) | Type::Struct(_)
+                ) {
+                    continue;
+                }
+
+                match types.get(entry.name.as_ref()) {
+                    Some((_, ty)) if **ty == entry.ty => {}
+                    Some((first, _)) => {
+                        let groups = duplicates
+                            .entry(entry.name.as_ref())
+                            .or_insert_with(|| Vec::from([first.to_string()]));
+                        groups.push(group.to_string());
+                    }
+                    None => {
+                        types.insert(entry.name.as_ref(), (group.as_ref(), &entry.ty));
+                    }
+                }
+            }
+        }
+
+        let mut issues = duplicates
+            .into_iter()
+            .map(|(name, groups)| TypeIssue::Duplicate {
+                name: name.to_string(),
+                groups,
+            })
+            .collect::<Vec<_>>();
+
+        let mut references = Vec::new();
+        for (group, definition) in definitions.iter() {
+            for entry in definition.entries.iter() {
+                collect_refs(
+                    &entry.ty,
+                    format!("{group}: {}", entry.name),
+                    &mut references,
+                );
+            }
+        }
+        issues.extend(
+            references
+                .into_iter()
+                .filter(|(name, _)| !types.contains_key(name.as_str()))
+                .map(|(name, path)| TypeIssue::Undefined { name, path }),
+        );
+
+        Self { types, issues }
+    }
+
+    /// Get the named type definition. i.e. the [`Type::Class`] for a class name
+    pub fn get(&self, name: impl AsRef<str>) -> Option<&'a Type> {
+        self.types.get(name.as_ref()).map(|(_, ty)| *ty)
+    }
+
+    /// Get the name of the definition group that first defines the named type
+    pub fn group(&self, name: impl AsRef<str>) -> Option<&'a str> {
+        self.types.get(name.as_ref()).map(|(group, _)| *group)
+    }
+
+    /// Check if a type with the name is defined
+    pub fn contains(&self, name: impl AsRef<str>) -> bool {
+        self.types.contains_key(name.as_ref())
+    }
+
+    /// Names of all defined types
+    pub fn names(&self) -> impl Iterator<Item = &'a str> + '_ {
+        self.types.keys().copied()
+    }
+
+    /// Resolve a [`Type::Ref`] to the type it names
+    ///
+    /// Other types are returned as is. Returns `None` if the referenced type is not defined.
+    pub fn resolve<'t>(&self, ty: &'t Type) -> Option<&'t Type>
+    where
+        'a: 't,
+    {
+        match ty {
+            Type::Ref(name) => self.get(name),
+            other => Some(other),
+        }
+    }
+
+    /// Undefined and duplicate types that were found
+    pub fn issues(&self) -> &[TypeIssue] {
+        &self.issues
+    }
+
+    /// Returns an error listing every issue if any type is undefined or duplicated
+    pub fn check(&self) -> mlua::Result<()> {
+        if self.issues.is_empty() {
+            return Ok(());
+        }
+
+        Err(mlua::Error::runtime(
+            self.issues
+                .iter()
+                .map(|issue| issue.to_string())
+                .collect::<Vec<_>>()
+                .join("\n"),
+        ))
+    }
+}
+
+/// Collect the name of every [`Type::Ref`] along with where it is used
+///
+/// References are not followed so types that reference each other are only visited once.
+fn collect_refs(ty: &Type, path: String, refs: &mut Vec<(String, String)>) {
+    match ty {
+        Type::Ref(name) => refs.push((name.to_string(), path)),
+        Type::Value(ty) | Type::Alias(ty) | Type::Variadic(ty) | Type::Array(ty) => {
+            collect_refs(ty, path, refs)
+        }
+        Type::Enum(_, types) | Type::Tuple(types) | Type::Union(types) => {
+            for ty in types {
+                collect_refs(ty, path.clone(), refs);
+            }
+        }
+        Type::Struct(fields) => {
+            for (name, ty) in fields {
+                collect_refs(ty, format!("{path}.{name}"), refs);
+            }
+        }
+        Type::Map(key, value) => {
+            collect_refs(key, path.clone(), refs);
+            collect_refs(value, path, refs);
+        }
+        Type::Function {
+            params, returns, ..
+        } => {
+            for param in params {
+                collect_refs(&param.ty, path.clone(), refs);
+            }
+            for ret in returns {
+                collect_refs(&ret.ty, path.clone(), refs);
+            }
+        }
+        Type::Class(class) => collect_class_refs(class, &path, refs),
+        Type::Module(module) => collect_module_refs(module, &path, refs),
+        Type::Single(_) => {}
+    }
+}
+
+fn collect_class_refs(class: &TypedClassBuilder, path: &str, refs: &mut Vec<(String, String)>) {
+    collect_field_refs(&class.fields, &format!("{path}."), refs);
+    collect_field_refs(&class.static_fields, &format!("{path}."), refs);
+    collect_field_refs(&class.meta_fields, &format!("{path}.__metatable."), refs);
+    collect_func_refs(&class.functions, &format!("{path}."), refs);
+    collect_func_refs(&class.methods, &format!("{path}:"), refs);
+    collect_func_refs(&class.meta_functions, &format!("{path}.__metatable."), refs);
+    collect_func_refs(&class.meta_methods, &format!("{path}.__metatable:"), refs);
+    collect_index_refs(&class.index_fields, path, refs);
+}
+
+fn collect_module_refs(module: &TypedModuleBuilder, path: &str, refs: &mut Vec<(String, String)>) {
+    collect_field_refs(&module.fields, &format!("{path}."), refs);
+    collect_field_refs(&module.meta_fields, &format!("{path}.__metatable."), refs);
+    collect_func_refs(&module.functions, &format!("{path}."), refs);
+    collect_func_refs(&module.methods, &format!("{path}:"), refs);
+    collect_func_refs(
+        &module.meta_functions,
+        &format!("{path}.__metatable."),
+        refs,
+    );
+    collect_func_refs(&module.meta_methods, &format!("{path}.__metatable:"), refs);
+    collect_index_refs(&module.index_fields, path, refs);
+    for (name, nested) in module.nested_modules.iter() {
+        collect_module_refs(nested, &format!("{path}.{name}"), refs);
+    }
+}
+
+fn collect_field_refs<'k>(
+    fields: impl IntoIterator<Item = (&'k std::borrow::Cow<'static, str>, &'k Field)>,
+    prefix: &str,
+    refs: &mut Vec<(String, String)>,
+) {
+    for (name, field) in fields {
+        collect_refs(&field.ty, format!("{prefix}{name}"), refs);
+    }
+}
+
+fn collect_func_refs<'k>(
+    funcs: impl IntoIterator<Item = (&'k std::borrow::Cow<'static, str>, &'k Func)>,
+    prefix: &str,
+    refs: &mut Vec<(String, String)>,
+) {
+    for (name, func) in funcs {
+        let path = format!("{prefix}{name}");
+        for param in func.params.iter() {
+            collect_refs(&param.ty, path.clone(), refs);
+        }
+        for ret in func.returns.iter() {
+            collect_refs(&ret.ty, path.clone(), refs);
+        }
+    }
+}
+
+fn collect_index_refs(
+    fields: &BTreeMap<Type, Field>,
+    path: &str,
+    refs: &mut Vec<(String, String)>,
+) {
+    for (key, field) in fields {
+        let path = format!("{path}[{key}]");
+        collect_refs(key, path.clone(), refs);
+        collect_refs(&field.ty, path, refs);
+    }
+}
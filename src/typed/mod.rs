@@ -3,21 +3,36 @@ pub mod generator;
 
 mod class;
 mod module;
+mod registry;
+mod table;
+mod thread;
+mod ty;
 
 pub use class::{
     TypedClassBuilder, TypedDataFields, TypedDataMethods, TypedUserData, WrappedBuilder,
 };
 pub use module::{TypedModule, TypedModuleBuilder, TypedModuleFields, TypedModuleMethods};
+pub use registry::TypedRegistry;
+pub use table::{TypedRecord, TypedTable};
+pub use thread::{Resumed, TypedThread};
 
 use std::{
     borrow::Cow,
-    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+    num::{
+        NonZeroI128, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI8, NonZeroIsize, NonZeroU128,
+        NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU8, NonZeroUsize,
+    },
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::Arc,
+    time::Duration,
 };
 
 use function::Return;
-pub use function::{Param, TypedFunction};
+pub use function::{Param, SplitParams, TypedAsyncFunction, TypedFunction};
 
-use mlua::Variadic;
+use mlua::{Either, MultiValue, UserDataRef, UserDataRefMut, Variadic};
 
 /// Add a lua [`Type`] representation to a rust type
 pub trait Typed {
@@ -94,9 +109,15 @@ macro_rules! impl_static_typed_generic_with_lifetime {
 impl_static_typed! {
     mlua::LightUserData => "lightuserdata",
     mlua::Error => "error",
-    String | &str => "string",
+    mlua::BString => "string",
+    String | &str | char => "string",
+    PathBuf | &Path => "string",
     u8 | u16 | u32 | u64 | usize | u128 | i8 | i16 | i32 | i64 | isize | i128 => "integer",
+    NonZeroU8 | NonZeroU16 | NonZeroU32 | NonZeroU64 | NonZeroUsize | NonZeroU128 => "integer",
+    NonZeroI8 | NonZeroI16 | NonZeroI32 | NonZeroI64 | NonZeroIsize | NonZeroI128 => "integer",
     f32 | f64 => "number",
+    // Represented as a number of seconds
+    Duration => "number",
     bool => "boolean",
 }
 
@@ -108,6 +129,7 @@ impl_static_typed_generic! {
     for mlua::Function => "fun()",
     for mlua::AnyUserData => "userdata",
     for mlua::String => "string",
+    for mlua::Table => "table",
     for mlua::Thread => "thread",
     for mlua::Value => "any"
 }
@@ -128,6 +150,22 @@ impl<T: Typed> Typed for Variadic<T> {
     }
 }
 
+/// ...any
+impl Typed for MultiValue {
+    fn ty() -> Type {
+        Type::Variadic(Type::single("any").into())
+    }
+
+    /// @param ... any
+    fn as_param() -> Param {
+        Param {
+            doc: None,
+            name: Some("...".into()),
+            ty: Type::single("any"),
+        }
+    }
+}
+
 /// {type} | nil
 impl<T: Typed> Typed for Option<T> {
     fn ty() -> Type {
@@ -135,6 +173,45 @@ impl<T: Typed> Typed for Option<T> {
     }
 }
 
+/// {left} | {right}
+impl<L: Typed, R: Typed> Typed for Either<L, R> {
+    fn ty() -> Type {
+        L::ty() | R::ty()
+    }
+}
+
+// Wrapper types that are represented by their inner type
+
+macro_rules! impl_transparent_typed {
+    ($($target: ident),* $(,)?) => {
+        $(
+            impl<T: Typed> Typed for $target<T> {
+                fn ty() -> Type {
+                    T::ty()
+                }
+
+                fn as_param() -> Param {
+                    T::as_param()
+                }
+            }
+        )*
+    };
+}
+
+impl_transparent_typed!(Box, Rc, Arc);
+
+impl<T: Typed + 'static> Typed for UserDataRef<T> {
+    fn ty() -> Type {
+        T::ty()
+    }
+}
+
+impl<T: Typed + 'static> Typed for UserDataRefMut<T> {
+    fn ty() -> Type {
+        T::ty()
+    }
+}
+
 impl From<&'static str> for Type {
     fn from(value: &'static str) -> Self {
         Type::Single(value.into())
@@ -170,6 +247,11 @@ impl<I: Typed> Typed for &[I] {
         Type::Array(I::ty().into())
     }
 }
+impl<I: Typed> Typed for VecDeque<I> {
+    fn ty() -> Type {
+        Type::Array(I::ty().into())
+    }
+}
 impl<I: Typed> Typed for HashSet<I> {
     fn ty() -> Type {
         Type::Array(I::ty().into())
@@ -202,6 +284,17 @@ where
     }
 }
 
+#[cfg(feature = "indexmap")]
+impl<K, V> Typed for indexmap::IndexMap<K, V>
+where
+    K: Typed,
+    V: Typed,
+{
+    fn ty() -> Type {
+        Type::Map(K::ty().into(), V::ty().into())
+    }
+}
+
 /// Representation of a lua type for a rust type
 #[derive(Debug, Clone, PartialEq, strum::AsRefStr, PartialOrd, Eq, Ord)]
 pub enum Type {
@@ -212,6 +305,10 @@ pub enum Type {
     /// 3
     /// ... etc
     Single(Cow<'static, str>),
+    /// Reference to a named type that is defined elsewhere. i.e. a class, alias, or enum
+    ///
+    /// Resolve references with a [`TypeRegistry`][crate::typed::generator::TypeRegistry]
+    Ref(Cow<'static, str>),
     Value(Box<Type>),
     /// --- @alias {name} <type>
     Alias(Box<Type>),
@@ -238,7 +335,8 @@ pub enum Type {
     Module(Box<TypedModuleBuilder>),
     /// { [1]: <type>, [2]: <type>, ...etc }
     Tuple(Vec<Type>),
-    Struct(BTreeMap<&'static str, Type>),
+    /// { name: <type>, other: <type>, ...etc }
+    Struct(BTreeMap<Cow<'static, str>, Type>),
     Variadic(Box<Type>),
     Union(Vec<Type>),
     Array(Box<Type>),
@@ -246,6 +344,8 @@ pub enum Type {
     Function {
         params: Vec<Param>,
         returns: Vec<Return>,
+        /// Whether the function can yield, i.e. created from an async rust function
+        is_async: bool,
     },
 }
 
@@ -304,6 +404,11 @@ impl Type {
         Self::Single(value.into())
     }
 
+    /// Create a reference to a named type. i.e. a class, alias, or enum
+    pub fn reference(name: impl Into<Cow<'static, str>>) -> Self {
+        Self::Ref(name.into())
+    }
+
     /// Create an enum type. This is equal to an [`alias`][crate::typed::Type::Alias]
     pub fn r#enum(
         name: impl Into<Cow<'static, str>>,
@@ -322,7 +427,7 @@ impl Type {
         Self::Variadic(Box::new(ty))
     }
 
-    /// Create a type that is an array. i.e. `{ [integer]: type }`
+    /// Create a type that is an array. i.e. `type[]`
     pub fn array(ty: Type) -> Self {
         Self::Array(Box::new(ty))
     }
@@ -355,6 +460,7 @@ impl Type {
                 .into_iter()
                 .map(|ty| Return { doc: None, ty })
                 .collect(),
+            is_async: false,
         }
     }
 }
@@ -435,6 +541,30 @@ where
     }
 }
 
+/// {type}?, {error}?
+///
+/// mlua returns `nil` and the error as a second value when the result is an [`Err`]
+impl<T: Typed, E: Typed> TypedMultiValue for Result<T, E> {
+    fn get_types_as_params() -> Vec<Param> {
+        Vec::from([
+            Param {
+                doc: None,
+                name: None,
+                ty: Type::Union(vec![T::ty(), Type::Single("nil".into())]),
+            },
+            Param {
+                doc: None,
+                name: None,
+                ty: Type::Union(vec![E::ty(), Type::Single("nil".into())]),
+            },
+        ])
+    }
+}
+
+// Tuples are only `TypedMultiValue` and not `Typed`. mlua converts them to multiple values
+// instead of a table and a `Typed` impl would overlap with the blanket impl above. Use
+// `Type::tuple` for tables shaped like tuples.
+
 impl_typed_multi_value!(A B C D E F G H I J K L M N O P);
 impl_typed_multi_value!(A B C D E F G H I J K L M N O);
 impl_typed_multi_value!(A B C D E F G H I J K L M N);
@@ -460,10 +590,34 @@ pub struct Field {
     pub doc: Option<Cow<'static, str>>,
 }
 
+impl Field {
+    /// Create a field with an optional doc comment
+    pub fn new(ty: Type, doc: Option<Cow<'static, str>>) -> Self {
+        Self { ty, doc }
+    }
+
+    /// Check if the field may be missing, written as `--- @field name? <type>`. i.e. from an `Option<T>`
+    pub fn is_optional(&self) -> bool {
+        self.ty.is_optional()
+    }
+}
+
 /// Type information for a lua `class` function
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Func {
     pub params: Vec<Param>,
     pub returns: Vec<Return>,
     pub doc: Option<Cow<'static, str>>,
+    /// Whether the function is async and is marked with `@async`
+    pub is_async: bool,
+}
+
+/// Name of a rust type without it's module path or generics. i.e. `my_crate::Player<T>` is `Player`
+///
+/// This is the name `#[derive(Typed)]` references the type by, so classes and records need to be
+/// registered with it.
+pub(crate) fn short_type_name<T: ?Sized>() -> &'static str {
+    let name = std::any::type_name::<T>();
+    let name = name.split_once('<').map(|v| v.0).unwrap_or(name);
+    name.rsplit_once("::").map(|v| v.1).unwrap_or(name)
 }
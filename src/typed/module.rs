@@ -1,11 +1,13 @@
 use std::{any::type_name, borrow::Cow, collections::BTreeMap};
 
-use super::{generator::FunctionBuilder, Field, Func, Typed, TypedMultiValue};
+use super::{
+    function::Signature, generator::FunctionBuilder, Field, Func, Type, Typed, TypedMultiValue,
+};
 use crate::{
     extras::{Module, ModuleFields, ModuleMethods},
     MaybeSend,
 };
-use mlua::{FromLuaMulti, IntoLua, IntoLuaMulti};
+use mlua::{FromLuaMulti, IntoLua, IntoLuaMulti, MultiValue};
 
 /// Builder that constructs type and documentation information for a module using the [`TypedModule`] trait
 #[derive(Default, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
@@ -22,6 +24,9 @@ pub struct TypedModuleBuilder {
     pub meta_functions: BTreeMap<Cow<'static, str>, Func>,
     pub meta_methods: BTreeMap<Cow<'static, str>, Func>,
 
+    /// Index signatures keyed by the key type. i.e. `--- @field [string] integer`
+    pub index_fields: BTreeMap<Type, Field>,
+
     queued_doc: Option<String>,
     parents: Vec<&'static str>,
 }
@@ -43,6 +48,7 @@ impl TypedModuleBuilder {
     #[inline]
     pub fn is_empty(&self) -> bool {
         self.fields.is_empty()
+            && self.index_fields.is_empty()
             && self.nested_modules.is_empty()
             && self.functions.is_empty()
             && self.methods.is_empty()
@@ -78,6 +84,13 @@ pub trait TypedModuleFields {
     fn add_module<V>(&mut self, name: impl AsRef<str>) -> mlua::Result<()>
     where
         V: TypedModule;
+
+    /// Document the keys that are handled by an `__index` meta field or function. i.e. `--- @field [string] integer`
+    ///
+    /// This only collects type information, the values are still provided by the meta field
+    fn add_index_field<K: Typed, V: Typed>(&mut self) -> mlua::Result<()> {
+        Ok(())
+    }
 }
 
 /// Typed variant of [`ModuleMethods`]
@@ -184,6 +197,64 @@ pub trait TypedModuleMethods {
         A: FromLuaMulti + TypedMultiValue,
         R: IntoLuaMulti + TypedMultiValue,
         G: Fn(&mut FunctionBuilder<A, R>);
+
+    #[cfg(feature = "async")]
+    /// Typed variant of [`add_async_function`][ModuleMethods::add_async_function] only collecting the type information
+    fn add_async_function<K, F, A, R, FR>(&mut self, name: K, function: F) -> mlua::Result<()>
+    where
+        K: AsRef<str>,
+        F: Fn(mlua::Lua, A) -> FR + MaybeSend + 'static,
+        A: FromLuaMulti + TypedMultiValue,
+        R: IntoLuaMulti + TypedMultiValue,
+        FR: std::future::Future<Output = mlua::Result<R>> + MaybeSend + 'static;
+
+    #[cfg(feature = "async")]
+    /// Typed variant of [`add_async_function`][ModuleMethods::add_async_function] only collecting the type information
+    ///
+    /// Pass an additional callback that allows for param names, param doc comments, and return doc
+    /// comments to be specified.
+    fn add_async_function_with<K, F, A, R, FR, G>(
+        &mut self,
+        name: K,
+        function: F,
+        generator: G,
+    ) -> mlua::Result<()>
+    where
+        K: AsRef<str>,
+        F: Fn(mlua::Lua, A) -> FR + MaybeSend + 'static,
+        A: FromLuaMulti + TypedMultiValue,
+        R: IntoLuaMulti + TypedMultiValue,
+        FR: std::future::Future<Output = mlua::Result<R>> + MaybeSend + 'static,
+        G: Fn(&mut FunctionBuilder<A, R>);
+
+    #[cfg(feature = "async")]
+    /// Typed variant of [`add_async_method`][ModuleMethods::add_async_method] only collecting the type information
+    fn add_async_method<K, F, A, R, FR>(&mut self, name: K, function: F) -> mlua::Result<()>
+    where
+        K: AsRef<str>,
+        F: Fn(mlua::Lua, mlua::Table, A) -> FR + MaybeSend + 'static,
+        A: FromLuaMulti + TypedMultiValue,
+        R: IntoLuaMulti + TypedMultiValue,
+        FR: std::future::Future<Output = mlua::Result<R>> + MaybeSend + 'static;
+
+    #[cfg(feature = "async")]
+    /// Typed variant of [`add_async_method`][ModuleMethods::add_async_method] only collecting the type information
+    ///
+    /// Pass an additional callback that allows for param names, param doc comments, and return doc
+    /// comments to be specified.
+    fn add_async_method_with<K, F, A, R, FR, G>(
+        &mut self,
+        name: K,
+        function: F,
+        generator: G,
+    ) -> mlua::Result<()>
+    where
+        K: AsRef<str>,
+        F: Fn(mlua::Lua, mlua::Table, A) -> FR + MaybeSend + 'static,
+        A: FromLuaMulti + TypedMultiValue,
+        R: IntoLuaMulti + TypedMultiValue,
+        FR: std::future::Future<Output = mlua::Result<R>> + MaybeSend + 'static,
+        G: Fn(&mut FunctionBuilder<A, R>);
 }
 
 pub struct WrappedModule<'module, M>(pub &'module mut M);
@@ -228,15 +299,17 @@ impl<'module, M: ModuleMethods> TypedModuleMethods for WrappedModule<'module, M>
         A: FromLuaMulti + TypedMultiValue,
         R: IntoLuaMulti + TypedMultiValue,
     {
-        self.0
-            .add_function::<&str, F, A, R>(name.as_ref(), function)
+        let signature = Signature::function::<A, R>(name.as_ref());
+        self.0.add_function(name.as_ref(), move |lua, args: MultiValue| {
+            function(lua, signature.from_lua_args::<A>(args, lua)?)
+        })
     }
 
     fn add_function_with<K, F, A, R, G>(
         &mut self,
         name: K,
         function: F,
-        _generator: G,
+        generator: G,
     ) -> mlua::Result<()>
     where
         K: AsRef<str>,
@@ -245,8 +318,10 @@ impl<'module, M: ModuleMethods> TypedModuleMethods for WrappedModule<'module, M>
         R: IntoLuaMulti + TypedMultiValue,
         G: Fn(&mut FunctionBuilder<A, R>),
     {
-        self.0
-            .add_function::<&str, F, A, R>(name.as_ref(), function)
+        let signature = Signature::function::<A, R>(name.as_ref()).with(generator);
+        self.0.add_function(name.as_ref(), move |lua, args: MultiValue| {
+            function(lua, signature.from_lua_args::<A>(args, lua)?)
+        })
     }
 
     fn add_meta_function<K, F, A, R>(&mut self, name: K, function: F) -> mlua::Result<()>
@@ -256,15 +331,17 @@ impl<'module, M: ModuleMethods> TypedModuleMethods for WrappedModule<'module, M>
         A: FromLuaMulti + TypedMultiValue,
         R: IntoLuaMulti + TypedMultiValue,
     {
-        self.0
-            .add_meta_function::<&str, F, A, R>(name.as_ref(), function)
+        let signature = Signature::function::<A, R>(name.as_ref());
+        self.0.add_meta_function(name.as_ref(), move |lua, args: MultiValue| {
+            function(lua, signature.from_lua_args::<A>(args, lua)?)
+        })
     }
 
     fn add_meta_function_with<K, F, A, R, G>(
         &mut self,
         name: K,
         function: F,
-        _generator: G,
+        generator: G,
     ) -> mlua::Result<()>
     where
         K: AsRef<str>,
@@ -273,8 +350,10 @@ impl<'module, M: ModuleMethods> TypedModuleMethods for WrappedModule<'module, M>
         R: IntoLuaMulti + TypedMultiValue,
         G: Fn(&mut FunctionBuilder<A, R>),
     {
-        self.0
-            .add_meta_function::<&str, F, A, R>(name.as_ref(), function)
+        let signature = Signature::function::<A, R>(name.as_ref()).with(generator);
+        self.0.add_meta_function(name.as_ref(), move |lua, args: MultiValue| {
+            function(lua, signature.from_lua_args::<A>(args, lua)?)
+        })
     }
 
     fn add_method<K, F, A, R>(&mut self, name: K, function: F) -> mlua::Result<()>
@@ -284,14 +363,17 @@ impl<'module, M: ModuleMethods> TypedModuleMethods for WrappedModule<'module, M>
         A: FromLuaMulti + TypedMultiValue,
         R: IntoLuaMulti + TypedMultiValue,
     {
-        self.0.add_method::<&str, F, A, R>(name.as_ref(), function)
+        let signature = Signature::method::<A, R>(name.as_ref());
+        self.0.add_method(name.as_ref(), move |lua, this, args: MultiValue| {
+            function(lua, this, signature.from_lua_args::<A>(args, lua)?)
+        })
     }
 
     fn add_method_with<K, F, A, R, G>(
         &mut self,
         name: K,
         function: F,
-        _generator: G,
+        generator: G,
     ) -> mlua::Result<()>
     where
         K: AsRef<str>,
@@ -300,7 +382,10 @@ impl<'module, M: ModuleMethods> TypedModuleMethods for WrappedModule<'module, M>
         R: IntoLuaMulti + TypedMultiValue,
         G: Fn(&mut FunctionBuilder<A, R>),
     {
-        self.0.add_method::<&str, F, A, R>(name.as_ref(), function)
+        let signature = Signature::method::<A, R>(name.as_ref()).with(generator);
+        self.0.add_method(name.as_ref(), move |lua, this, args: MultiValue| {
+            function(lua, this, signature.from_lua_args::<A>(args, lua)?)
+        })
     }
 
     fn add_meta_method<K, F, A, R>(&mut self, name: K, function: F) -> mlua::Result<()>
@@ -310,24 +395,133 @@ impl<'module, M: ModuleMethods> TypedModuleMethods for WrappedModule<'module, M>
         A: FromLuaMulti + TypedMultiValue,
         R: IntoLuaMulti + TypedMultiValue,
     {
-        self.0.add_meta_method::<&str, F, A, R>(name.as_ref(), function)
+        let signature = Signature::method::<A, R>(name.as_ref());
+        self.0.add_meta_method(name.as_ref(), move |lua, this, args: MultiValue| {
+            function(lua, this, signature.from_lua_args::<A>(args, lua)?)
+        })
     }
 
     fn add_meta_method_with<K, F, A, R, G>(
         &mut self,
         name: K,
         function: F,
-        _generator: G,
+        generator: G,
     ) -> mlua::Result<()>
     where
         K: AsRef<str>,
         F: Fn(&mlua::Lua, mlua::Table, A) -> mlua::Result<R> + MaybeSend + 'static,
         A: FromLuaMulti + TypedMultiValue,
         R: IntoLuaMulti + TypedMultiValue,
+        G: Fn(&mut FunctionBuilder<A, R>),
+    {
+        let signature = Signature::method::<A, R>(name.as_ref()).with(generator);
+        self.0.add_meta_method(name.as_ref(), move |lua, this, args: MultiValue| {
+            function(lua, this, signature.from_lua_args::<A>(args, lua)?)
+        })
+    }
+
+    #[cfg(feature = "async")]
+    fn add_async_function<K, F, A, R, FR>(&mut self, name: K, function: F) -> mlua::Result<()>
+    where
+        K: AsRef<str>,
+        F: Fn(mlua::Lua, A) -> FR + MaybeSend + 'static,
+        A: FromLuaMulti + TypedMultiValue,
         R: IntoLuaMulti + TypedMultiValue,
+        FR: std::future::Future<Output = mlua::Result<R>> + MaybeSend + 'static,
     {
-        self.0
-            .add_meta_method::<&str, F, A, R>(name.as_ref(), function)
+        let signature = Signature::function::<A, R>(name.as_ref());
+        self.0.add_async_function(name.as_ref(), move |lua, args: MultiValue| {
+            let fut = signature
+                .from_lua_args::<A>(args, &lua)
+                .map(|args| function(lua, args));
+            async move {
+                match fut {
+                    Ok(fut) => fut.await,
+                    Err(err) => Err(err),
+                }
+            }
+        })
+    }
+
+    #[cfg(feature = "async")]
+    fn add_async_function_with<K, F, A, R, FR, G>(
+        &mut self,
+        name: K,
+        function: F,
+        generator: G,
+    ) -> mlua::Result<()>
+    where
+        K: AsRef<str>,
+        F: Fn(mlua::Lua, A) -> FR + MaybeSend + 'static,
+        A: FromLuaMulti + TypedMultiValue,
+        R: IntoLuaMulti + TypedMultiValue,
+        FR: std::future::Future<Output = mlua::Result<R>> + MaybeSend + 'static,
+        G: Fn(&mut FunctionBuilder<A, R>),
+    {
+        let signature = Signature::function::<A, R>(name.as_ref()).with(generator);
+        self.0.add_async_function(name.as_ref(), move |lua, args: MultiValue| {
+            let fut = signature
+                .from_lua_args::<A>(args, &lua)
+                .map(|args| function(lua, args));
+            async move {
+                match fut {
+                    Ok(fut) => fut.await,
+                    Err(err) => Err(err),
+                }
+            }
+        })
+    }
+
+    #[cfg(feature = "async")]
+    fn add_async_method<K, F, A, R, FR>(&mut self, name: K, function: F) -> mlua::Result<()>
+    where
+        K: AsRef<str>,
+        F: Fn(mlua::Lua, mlua::Table, A) -> FR + MaybeSend + 'static,
+        A: FromLuaMulti + TypedMultiValue,
+        R: IntoLuaMulti + TypedMultiValue,
+        FR: std::future::Future<Output = mlua::Result<R>> + MaybeSend + 'static,
+    {
+        let signature = Signature::method::<A, R>(name.as_ref());
+        self.0.add_async_method(name.as_ref(), move |lua, this, args: MultiValue| {
+            let fut = signature
+                .from_lua_args::<A>(args, &lua)
+                .map(|args| function(lua, this, args));
+            async move {
+                match fut {
+                    Ok(fut) => fut.await,
+                    Err(err) => Err(err),
+                }
+            }
+        })
+    }
+
+    #[cfg(feature = "async")]
+    fn add_async_method_with<K, F, A, R, FR, G>(
+        &mut self,
+        name: K,
+        function: F,
+        generator: G,
+    ) -> mlua::Result<()>
+    where
+        K: AsRef<str>,
+        F: Fn(mlua::Lua, mlua::Table, A) -> FR + MaybeSend + 'static,
+        A: FromLuaMulti + TypedMultiValue,
+        R: IntoLuaMulti + TypedMultiValue,
+        FR: std::future::Future<Output = mlua::Result<R>> + MaybeSend + 'static,
+        G: Fn(&mut FunctionBuilder<A, R>),
+    {
+        let signature = Signature::method::<A, R>(name.as_ref()).with(generator);
+        self.0.add_async_method(name.as_ref(), move |lua, this, args: MultiValue| {
+            let fut = signature
+                .from_lua_args::<A>(args, &lua)
+                .map(|args| function(lua, this, args));
+            async move {
+                match fut {
+                    Ok(fut) => fut.await,
+                    Err(err) => Err(err),
+                }
+            }
+        })
     }
 }
 
@@ -376,10 +570,7 @@ impl TypedModuleFields for TypedModuleBuilder {
     {
         self.fields.insert(
             name.as_ref().to_string().into(),
-            Field {
-                ty: V::ty(),
-                doc: self.queued_doc.take().map(|v| v.into()),
-            },
+            Field::new(V::ty(), self.queued_doc.take().map(|v| v.into())),
         );
         Ok(())
     }
@@ -391,10 +582,15 @@ impl TypedModuleFields for TypedModuleBuilder {
     {
         self.meta_fields.insert(
             name.as_ref().to_string().into(),
-            Field {
-                ty: V::ty(),
-                doc: self.queued_doc.take().map(|v| v.into()),
-            },
+            Field::new(V::ty(), self.queued_doc.take().map(|v| v.into())),
+        );
+        Ok(())
+    }
+
+    fn add_index_field<K: Typed, V: Typed>(&mut self) -> mlua::Result<()> {
+        self.index_fields.insert(
+            K::ty(),
+            Field::new(V::ty(), self.queued_doc.take().map(|v| v.into())),
         );
         Ok(())
     }
@@ -419,6 +615,7 @@ impl TypedModuleMethods for TypedModuleBuilder {
                 params: A::get_types_as_params(),
                 returns: R::get_types_as_returns(),
                 doc: self.queued_doc.take().map(|v| v.into()),
+                is_async: false,
             },
         );
         Ok(())
@@ -446,6 +643,7 @@ impl TypedModuleMethods for TypedModuleBuilder {
                 params: builder.params,
                 returns: builder.returns,
                 doc: self.queued_doc.take().map(|v| v.into()),
+                is_async: builder.is_async,
             },
         );
         Ok(())
@@ -464,6 +662,7 @@ impl TypedModuleMethods for TypedModuleBuilder {
                 params: A::get_types_as_params(),
                 returns: R::get_types_as_returns(),
                 doc: self.queued_doc.take().map(|v| v.into()),
+                is_async: false,
             },
         );
         Ok(())
@@ -491,6 +690,7 @@ impl TypedModuleMethods for TypedModuleBuilder {
                 params: builder.params,
                 returns: builder.returns,
                 doc: self.queued_doc.take().map(|v| v.into()),
+                is_async: builder.is_async,
             },
         );
         Ok(())
@@ -509,6 +709,7 @@ impl TypedModuleMethods for TypedModuleBuilder {
                 params: A::get_types_as_params(),
                 returns: R::get_types_as_returns(),
                 doc: self.queued_doc.take().map(|v| v.into()),
+                is_async: false,
             },
         );
         Ok(())
@@ -536,6 +737,7 @@ impl TypedModuleMethods for TypedModuleBuilder {
                 params: builder.params,
                 returns: builder.returns,
                 doc: self.queued_doc.take().map(|v| v.into()),
+                is_async: builder.is_async,
             },
         );
         Ok(())
@@ -554,6 +756,7 @@ impl TypedModuleMethods for TypedModuleBuilder {
                 params: A::get_types_as_params(),
                 returns: R::get_types_as_returns(),
                 doc: self.queued_doc.take().map(|v| v.into()),
+                is_async: false,
             },
         );
         Ok(())
@@ -581,6 +784,109 @@ impl TypedModuleMethods for TypedModuleBuilder {
                 params: builder.params,
                 returns: builder.returns,
                 doc: self.queued_doc.take().map(|v| v.into()),
+                is_async: builder.is_async,
+            },
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "async")]
+    fn add_async_function<K, F, A, R, FR>(&mut self, name: K, _function: F) -> mlua::Result<()>
+    where
+        K: AsRef<str>,
+        F: Fn(mlua::Lua, A) -> FR + MaybeSend + 'static,
+        A: FromLuaMulti + TypedMultiValue,
+        R: IntoLuaMulti + TypedMultiValue,
+        FR: std::future::Future<Output = mlua::Result<R>> + MaybeSend + 'static,
+    {
+        self.functions.insert(
+            name.as_ref().to_string().into(),
+            Func {
+                params: A::get_types_as_params(),
+                returns: R::get_types_as_returns(),
+                doc: self.queued_doc.take().map(|v| v.into()),
+                is_async: true,
+            },
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "async")]
+    fn add_async_function_with<K, F, A, R, FR, G>(
+        &mut self,
+        name: K,
+        _function: F,
+        generator: G,
+    ) -> mlua::Result<()>
+    where
+        K: AsRef<str>,
+        F: Fn(mlua::Lua, A) -> FR + MaybeSend + 'static,
+        A: FromLuaMulti + TypedMultiValue,
+        R: IntoLuaMulti + TypedMultiValue,
+        FR: std::future::Future<Output = mlua::Result<R>> + MaybeSend + 'static,
+        G: Fn(&mut FunctionBuilder<A, R>),
+    {
+        let mut builder = FunctionBuilder::<A, R>::default();
+        generator(&mut builder);
+
+        self.functions.insert(
+            name.as_ref().to_string().into(),
+            Func {
+                params: builder.params,
+                returns: builder.returns,
+                doc: self.queued_doc.take().map(|v| v.into()),
+                is_async: true,
+            },
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "async")]
+    fn add_async_method<K, F, A, R, FR>(&mut self, name: K, _function: F) -> mlua::Result<()>
+    where
+        K: AsRef<str>,
+        F: Fn(mlua::Lua, mlua::Table, A) -> FR + MaybeSend + 'static,
+        A: FromLuaMulti + TypedMultiValue,
+        R: IntoLuaMulti + TypedMultiValue,
+        FR: std::future::Future<Output = mlua::Result<R>> + MaybeSend + 'static,
+    {
+        self.methods.insert(
+            name.as_ref().to_string().into(),
+            Func {
+                params: A::get_types_as_params(),
+                returns: R::get_types_as_returns(),
+                doc: self.queued_doc.take().map(|v| v.into()),
+                is_async: true,
+            },
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "async")]
+    fn add_async_method_with<K, F, A, R, FR, G>(
+        &mut self,
+        name: K,
+        _function: F,
+        generator: G,
+    ) -> mlua::Result<()>
+    where
+        K: AsRef<str>,
+        F: Fn(mlua::Lua, mlua::Table, A) -> FR + MaybeSend + 'static,
+        A: FromLuaMulti + TypedMultiValue,
+        R: IntoLuaMulti + TypedMultiValue,
+        FR: std::future::Future<Output = mlua::Result<R>> + MaybeSend + 'static,
+        G: Fn(&mut FunctionBuilder<A, R>),
+    {
+        let mut builder = FunctionBuilder::<A, R>::default();
+        generator(&mut builder);
+
+        self.methods.insert(
+            name.as_ref().to_string().into(),
+            Func {
+                params: builder.params,
+                returns: builder.returns,
+                doc: self.queued_doc.take().map(|v| v.into()),
+                is_async: true,
             },
         );
         Ok(())
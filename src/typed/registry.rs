@@ -0,0 +1,228 @@
+use std::borrow::Cow;
+
+use mlua::{FromLuaMulti, IntoLua, IntoLuaMulti, Lua, UserData};
+
+use crate::extras::Module;
+
+use super::{
+    function::IntoTypedFunction,
+    generator::{Definition, DefinitionBuilder, Entry, FunctionBuilder},
+    short_type_name, Type, Typed, TypedClassBuilder, TypedModule, TypedModuleBuilder,
+    TypedMultiValue, TypedUserData,
+};
+
+/// Installs values, functions, modules, and classes into a [`Lua`] instance's globals while
+/// recording the matching definition entries.
+///
+/// This keeps what is documented in the generated definitions the same as what is installed at
+/// runtime.
+///
+/// # Example
+///
+/// ```
+/// use mlua_extras::{
+///     mlua::Lua,
+///     typed::{generator::Definitions, TypedRegistry},
+/// };
+///
+/// let lua = Lua::new();
+/// let definition = TypedRegistry::new(&lua)
+///     .function::<String, ()>("greet", |_lua, name: String| {
+///         println!("Hello, {name}!");
+///         Ok(())
+///     })?
+///     .value("version", String::from("1.0.0"))?
+///     .finish();
+///
+/// let definitions = Definitions::start().define("init", definition).finish();
+/// ```
+pub struct TypedRegistry<'lua, 'def> {
+    lua: &'lua Lua,
+    definition: DefinitionBuilder<'def>,
+}
+
+impl<'lua, 'def> TypedRegistry<'lua, 'def> {
+    /// Create a new registry that installs into the given lua instance's globals
+    pub fn new(lua: &'lua Lua) -> Self {
+        Self {
+            lua,
+            definition: DefinitionBuilder::default(),
+        }
+    }
+
+    /// Get the lua instance that values are installed into
+    pub fn lua(&self) -> &'lua Lua {
+        self.lua
+    }
+
+    /// Install a global function and register it's definition
+    pub fn function<Params, Returns>(
+        mut self,
+        name: impl Into<Cow<'def, str>>,
+        function: impl IntoTypedFunction<Params, Returns>,
+    ) -> mlua::Result<Self>
+    where
+        Params: TypedMultiValue + IntoLuaMulti + FromLuaMulti,
+        Returns: TypedMultiValue + IntoLuaMulti + FromLuaMulti,
+    {
+        let name = name.into();
+        let function = function.into_typed_function(self.lua)?;
+        self.lua.globals().set(name.as_ref(), function.as_function())?;
+        self.definition = self.definition.function(name, &function);
+        Ok(self)
+    }
+
+    /// Same as [`function`][TypedRegistry::function] but with additional documentation
+    pub fn function_with<Params, Returns, G>(
+        mut self,
+        name: impl Into<Cow<'def, str>>,
+        function: impl IntoTypedFunction<Params, Returns>,
+        generator: G,
+    ) -> mlua::Result<Self>
+    where
+        Params: TypedMultiValue + IntoLuaMulti + FromLuaMulti,
+        Returns: TypedMultiValue + IntoLuaMulti + FromLuaMulti,
+        G: Fn(&mut FunctionBuilder<Params, Returns>),
+    {
+        let name = name.into();
+        let function = function.into_typed_function(self.lua)?;
+        self.lua.globals().set(name.as_ref(), function.as_function())?;
+        self.definition = self.definition.function_with(name, &function, generator);
+        Ok(self)
+    }
+
+    /// Install a global async function and register it's definition
+    ///
+    /// The function is annotated with `@async` in the generated definitions
+    #[cfg(feature = "async")]
+    pub fn async_function<Params, Returns, F, FR>(
+        mut self,
+        name: impl Into<Cow<'def, str>>,
+        function: F,
+    ) -> mlua::Result<Self>
+    where
+        Params: TypedMultiValue + IntoLuaMulti + FromLuaMulti,
+        Returns: TypedMultiValue + IntoLuaMulti + FromLuaMulti,
+        F: Fn(Lua, Params) -> FR + crate::MaybeSend + 'static,
+        FR: std::future::Future<Output = mlua::Result<Returns>> + crate::MaybeSend + 'static,
+    {
+        let name = name.into();
+        let function = super::TypedFunction::<Params, Returns>::from_async(self.lua, function)?;
+        self.lua.globals().set(name.as_ref(), function.as_function())?;
+        self.definition = self.definition.async_function(name, &function);
+        Ok(self)
+    }
+
+    /// Install a global value and register it's definition using the value's type
+    pub fn value<T: Typed + IntoLua>(
+        mut self,
+        name: impl Into<Cow<'def, str>>,
+        value: T,
+    ) -> mlua::Result<Self> {
+        let name = name.into();
+        self.lua.globals().set(name.as_ref(), value)?;
+        self.definition = self.definition.value::<T>(name);
+        Ok(self)
+    }
+
+    /// Same as [`value`][TypedRegistry::value] but with additional docs
+    pub fn value_with<T: Typed + IntoLua, S: Into<Cow<'def, str>>>(
+        mut self,
+        name: impl Into<Cow<'def, str>>,
+        value: T,
+        doc: Option<S>,
+    ) -> mlua::Result<Self> {
+        let name = name.into();
+        self.lua.globals().set(name.as_ref(), value)?;
+        self.definition = self.definition.value_with::<T, S>(name, doc);
+        Ok(self)
+    }
+
+    /// Install a global module table and register it's definition
+    pub fn module<M: TypedModule>(mut self, name: impl Into<Cow<'def, str>>) -> mlua::Result<Self> {
+        let name = name.into();
+        self.lua.globals().set(name.as_ref(), M::module())?;
+        self.definition.entries.push(Entry::new(
+            name,
+            Type::module(TypedModuleBuilder::new::<M>()?),
+        ));
+        Ok(self)
+    }
+
+    /// Same as [`module`][TypedRegistry::module] but with additional docs
+    pub fn module_with<M: TypedModule, S: Into<Cow<'def, str>>>(
+        mut self,
+        name: impl Into<Cow<'def, str>>,
+        doc: Option<S>,
+    ) -> mlua::Result<Self> {
+        let name = name.into();
+        self.lua.globals().set(name.as_ref(), M::module())?;
+        self.definition.entries.push(Entry::new_with(
+            name,
+            Type::module(TypedModuleBuilder::new::<M>()?),
+            doc,
+        ));
+        Ok(self)
+    }
+
+    /// Install a class as a global and register it's definition
+    ///
+    /// The global is a proxy of the class created with [`Lua::create_proxy`] which exposes the
+    /// class' functions, i.e. constructors, without an instance. The class is defined with the
+    /// name of the rust type, the same name `#[derive(Typed)]` references it by, and the global is
+    /// typed as that class.
+    pub fn class<T: TypedUserData + UserData + 'static>(
+        mut self,
+        name: impl Into<Cow<'def, str>>,
+    ) -> mlua::Result<Self> {
+        let name = name.into();
+        self.lua.globals().set(name.as_ref(), self.lua.create_proxy::<T>()?)?;
+
+        self.define_class::<T>();
+        self.definition.entries.push(Entry::new(
+            name,
+            Type::Value(Box::new(Type::reference(short_type_name::<T>()))),
+        ));
+        Ok(self)
+    }
+
+    /// Register a class definition without installing anything
+    ///
+    /// Useful for classes that are only ever passed to and returned from functions
+    pub fn register_class<T: TypedUserData>(mut self) -> Self {
+        self.define_class::<T>();
+        self
+    }
+
+    /// Add the class definition unless it was already added
+    fn define_class<T: TypedUserData>(&mut self) {
+        let name = short_type_name::<T>();
+        let defined = self
+            .definition
+            .entries
+            .iter()
+            .any(|entry| entry.name == name && matches!(entry.ty, Type::Class(_)));
+        if !defined {
+            self.definition.entries.push(Entry::new(
+                name,
+                Type::class(TypedClassBuilder::new::<T>()),
+            ));
+        }
+    }
+
+    /// Register an enum definition without installing anything
+    pub fn register_enum<T: Typed>(mut self) -> mlua::Result<Self> {
+        self.definition = self.definition.register_enum::<T>()?;
+        Ok(self)
+    }
+
+    /// Get the definition entries recorded so far
+    pub fn definition(&self) -> &DefinitionBuilder<'def> {
+        &self.definition
+    }
+
+    /// Finish installing and collect the recorded definition entries
+    pub fn finish(self) -> Definition<'def> {
+        self.definition.finish()
+    }
+}
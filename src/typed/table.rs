@@ -0,0 +1,274 @@
+use std::{borrow::Cow, collections::BTreeMap, marker::PhantomData, sync::Arc};
+
+use mlua::{FromLua, IntoLua, Lua, Table, Value};
+
+use super::{Type, Typed};
+
+/// Helper to bake the key and value type information for a lua [`Table`][mlua::Table]. This makes
+/// repeated access to the table's entries all the same with enforced key and value types.
+///
+/// # Example
+///
+/// ```
+/// use mlua_extras::{mlua::Lua, typed::TypedTable};
+///
+/// let lua = Lua::new();
+/// let scores = TypedTable::<String, u32>::new(&lua)?;
+/// scores.set("mlua".into(), 10)?;
+///
+/// for entry in scores.pairs() {
+///     let (name, score) = entry?;
+///     println!("{name}: {score}");
+/// }
+/// ```
+pub struct TypedTable<K, V> {
+    inner: Table,
+    _k: PhantomData<K>,
+    _v: PhantomData<V>,
+}
+
+impl<K, V> Clone for TypedTable<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            _k: PhantomData,
+            _v: PhantomData,
+        }
+    }
+}
+
+impl<K, V> std::fmt::Debug for TypedTable<K, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("TypedTable").field(&self.inner).finish()
+    }
+}
+
+impl<K, V> TypedTable<K, V> {
+    /// Create a new empty typed table
+    pub fn new(lua: &Lua) -> mlua::Result<Self> {
+        Ok(Self::from_table(lua.create_table()?))
+    }
+
+    /// Bake the key and value types into an existing table
+    pub fn from_table(table: Table) -> Self {
+        Self {
+            inner: table,
+            _k: PhantomData,
+            _v: PhantomData,
+        }
+    }
+
+    /// Get the underlying lua [`Table`][mlua::Table]
+    pub fn as_table(&self) -> &Table {
+        &self.inner
+    }
+
+    /// Unwrap the underlying lua [`Table`][mlua::Table]
+    pub fn into_inner(self) -> Table {
+        self.inner
+    }
+}
+
+impl<K, V> TypedTable<K, V>
+where
+    K: FromLua + IntoLua,
+    V: FromLua + IntoLua,
+{
+    /// Same as [Table::get] but with the key and value types already specified
+    pub fn get(&self, key: K) -> mlua::Result<V> {
+        self.inner.get::<V>(key)
+    }
+
+    /// Same as [Table::set] but with the key and value types already specified
+    pub fn set(&self, key: K, value: V) -> mlua::Result<()> {
+        self.inner.set(key, value)
+    }
+
+    /// Same as [Table::contains_key] but with the key type already specified
+    pub fn contains_key(&self, key: K) -> mlua::Result<bool> {
+        self.inner.contains_key(key)
+    }
+
+    /// Same as [Table::pairs] but with the key and value types already specified
+    pub fn pairs(&self) -> impl Iterator<Item = mlua::Result<(K, V)>> + '_ {
+        self.inner.pairs::<K, V>()
+    }
+
+    /// Same as [Table::sequence_values] but with the value type already specified
+    pub fn sequence_values(&self) -> impl Iterator<Item = mlua::Result<V>> + '_ {
+        self.inner.sequence_values::<V>()
+    }
+
+    /// Same as [Table::push] but with the value type already specified
+    pub fn push(&self, value: V) -> mlua::Result<()> {
+        self.inner.push(value)
+    }
+}
+
+impl<K, V> FromLua for TypedTable<K, V> {
+    fn from_lua(value: Value, lua: &Lua) -> mlua::Result<Self> {
+        Ok(Self::from_table(FromLua::from_lua(value, lua)?))
+    }
+}
+
+impl<K, V> IntoLua for TypedTable<K, V> {
+    fn into_lua(self, _lua: &Lua) -> mlua::Result<Value> {
+        Ok(Value::Table(self.inner))
+    }
+}
+
+/// { [key]: value }
+impl<K: Typed, V: Typed> Typed for TypedTable<K, V> {
+    fn ty() -> Type {
+        Type::Map(K::ty().into(), V::ty().into())
+    }
+}
+
+/// Record style wrapper around a lua [`Table`][mlua::Table] where the field types come from a
+/// [`Typed`] struct.
+///
+/// When the struct's type is a [`Type::Struct`], i.e. from `#[derive(Typed)]` with
+/// `#[typed(record)]`, field access is checked against the declared fields and their types.
+/// Reading an optional field requires an optional type, i.e. `Option<String>` for `string?`.
+///
+/// # Example
+///
+/// ```
+/// use mlua_extras::{mlua::Lua, typed::TypedRecord, Typed};
+///
+/// #[derive(Typed)]
+/// #[typed(record)]
+/// struct Options {
+///     name: String,
+///     verbose: bool,
+/// }
+///
+/// let lua = Lua::new();
+/// let options = lua.load("{ name = 'mlua', verbose = true }").eval::<TypedRecord<Options>>()?;
+/// let name = options.get::<String>("name")?;
+/// ```
+pub struct TypedRecord<T> {
+    inner: Table,
+    /// Declared fields of `T` so the type is only built once per record
+    fields: Option<Arc<BTreeMap<Cow<'static, str>, Type>>>,
+    _t: PhantomData<T>,
+}
+
+impl<T> Clone for TypedRecord<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            fields: self.fields.clone(),
+            _t: PhantomData,
+        }
+    }
+}
+
+impl<T> std::fmt::Debug for TypedRecord<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("TypedRecord").field(&self.inner).finish()
+    }
+}
+
+impl<T> TypedRecord<T> {
+    /// Get the underlying lua [`Table`][mlua::Table]
+    pub fn as_table(&self) -> &Table {
+        &self.inner
+    }
+
+    /// Unwrap the underlying lua [`Table`][mlua::Table]
+    pub fn into_inner(self) -> Table {
+        self.inner
+    }
+}
+
+impl<T: Typed> TypedRecord<T> {
+    /// Create a new empty record
+    pub fn new(lua: &Lua) -> mlua::Result<Self> {
+        Ok(Self::from_table(lua.create_table()?))
+    }
+
+    /// Bake the record type into an existing table
+    pub fn from_table(table: Table) -> Self {
+        let fields = match T::ty() {
+            Type::Struct(fields) => Some(Arc::new(fields)),
+            _ => None,
+        };
+        Self {
+            inner: table,
+            fields,
+            _t: PhantomData,
+        }
+    }
+
+    /// Get the declared type of a field if the record's type is a [`Type::Struct`]
+    pub fn field_type(field: impl AsRef<str>) -> Option<Type> {
+        match T::ty() {
+            Type::Struct(fields) => fields.get(field.as_ref()).cloned(),
+            _ => None,
+        }
+    }
+
+    /// Get the value of a field in the record
+    ///
+    /// Returns an error if the field's declared type can't be read as `R`
+    pub fn get<R: FromLua + Typed>(&self, field: impl AsRef<str>) -> mlua::Result<R> {
+        if let Some(ty) = self.check_field(field.as_ref())? {
+            if !ty.is_assignable_to(&R::ty()) {
+                return Err(Self::type_error(field.as_ref(), ty, &R::ty()));
+            }
+        }
+        self.inner.get::<R>(field.as_ref())
+    }
+
+    /// Set the value of a field in the record
+    ///
+    /// Returns an error if `R` can't be assigned to the field's declared type
+    pub fn set<R: IntoLua + Typed>(&self, field: impl AsRef<str>, value: R) -> mlua::Result<()> {
+        if let Some(ty) = self.check_field(field.as_ref())? {
+            if !R::ty().is_assignable_to(ty) {
+                return Err(Self::type_error(field.as_ref(), ty, &R::ty()));
+            }
+        }
+        self.inner.set(field.as_ref(), value)
+    }
+
+    /// Get the declared type of the field, returning an error if the record doesn't declare it
+    fn check_field(&self, field: &str) -> mlua::Result<Option<&Type>> {
+        match self.fields.as_deref() {
+            Some(fields) => match fields.get(field) {
+                Some(ty) => Ok(Some(ty)),
+                None => Err(mlua::Error::runtime(format!(
+                    "unknown field '{field}' in record: {}",
+                    std::any::type_name::<T>()
+                ))),
+            },
+            None => Ok(None),
+        }
+    }
+
+    fn type_error(field: &str, declared: &Type, used: &Type) -> mlua::Error {
+        mlua::Error::runtime(format!(
+            "field '{field}' in record {} is declared as `{declared}` but was used as `{used}`",
+            std::any::type_name::<T>()
+        ))
+    }
+}
+
+impl<T: Typed> FromLua for TypedRecord<T> {
+    fn from_lua(value: Value, lua: &Lua) -> mlua::Result<Self> {
+        Ok(Self::from_table(FromLua::from_lua(value, lua)?))
+    }
+}
+
+impl<T> IntoLua for TypedRecord<T> {
+    fn into_lua(self, _lua: &Lua) -> mlua::Result<Value> {
+        Ok(Value::Table(self.inner))
+    }
+}
+
+impl<T: Typed> Typed for TypedRecord<T> {
+    fn ty() -> Type {
+        T::ty()
+    }
+}
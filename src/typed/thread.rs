@@ -0,0 +1,217 @@
+use std::marker::PhantomData;
+
+use mlua::{
+    FromLua, FromLuaMulti, Function, IntoLua, IntoLuaMulti, Lua, MultiValue, Thread, ThreadStatus,
+    Value, WeakLua,
+};
+
+use crate::MaybeSend;
+
+use super::{
+    function::IntoTypedFunction, generator::DefinitionWriter, Param, Type, Typed, TypedFunction,
+    TypedMultiValue,
+};
+
+/// Values produced by resuming a [`TypedThread`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Resumed<Yield, Return> {
+    /// The coroutine called `coroutine.yield` with these values
+    Yield(Yield),
+    /// The coroutine finished and returned these values
+    Return(Return),
+}
+
+/// Helper to bake the type information for a lua [`Thread`][mlua::Thread] (coroutine).
+///
+/// Values passed to [`resume`][TypedThread::resume] must be `Resume`. Values the coroutine
+/// yields are checked against `Yield` and the values it returns are checked against `Return`.
+///
+/// # Example
+///
+/// ```
+/// use mlua_extras::{mlua::Lua, typed::{Resumed, TypedThread}};
+///
+/// let lua = Lua::new();
+/// let counter = lua.load(r#"
+///     function(limit)
+///         for i = 1, limit do coroutine.yield(i) end
+///         return "done"
+///     end
+/// "#).eval()?;
+///
+/// let thread = TypedThread::<u32, u32, String>::from_function(&lua, counter)?;
+/// assert_eq!(thread.resume(2)?, Resumed::Yield(1));
+/// ```
+pub struct TypedThread<Resume, Yield, Return> {
+    inner: Thread,
+    lua: WeakLua,
+    _m: PhantomData<fn(Resume) -> (Yield, Return)>,
+}
+
+impl<Resume, Yield, Return> Clone for TypedThread<Resume, Yield, Return> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            lua: self.lua.clone(),
+            _m: PhantomData,
+        }
+    }
+}
+
+impl<Resume, Yield, Return> TypedThread<Resume, Yield, Return> {
+    /// Create a new coroutine from a lua function
+    pub fn from_function(lua: &Lua, function: Function) -> mlua::Result<Self> {
+        Ok(Self::from_thread(lua, lua.create_thread(function)?))
+    }
+
+    /// Bake the resume, yield, and return types into an existing thread
+    pub fn from_thread(lua: &Lua, thread: Thread) -> Self {
+        Self {
+            inner: thread,
+            lua: lua.weak(),
+            _m: PhantomData,
+        }
+    }
+
+    /// Get the current status of the coroutine
+    pub fn status(&self) -> ThreadStatus {
+        self.inner.status()
+    }
+
+    /// Check if the coroutine can be resumed
+    pub fn is_resumable(&self) -> bool {
+        self.inner.status() == ThreadStatus::Resumable
+    }
+
+    /// Unwrap the underlying lua [`Thread`][mlua::Thread]
+    pub fn into_inner(self) -> Thread {
+        self.inner
+    }
+}
+
+impl<Resume, Yield, Return> TypedThread<Resume, Yield, Return>
+where
+    Resume: TypedMultiValue + IntoLuaMulti + FromLuaMulti,
+    Return: TypedMultiValue + IntoLuaMulti + FromLuaMulti,
+{
+    /// Create a new coroutine from a typed function
+    pub fn new(lua: &Lua, function: impl IntoTypedFunction<Resume, Return>) -> mlua::Result<Self> {
+        let function = function.into_typed_function(lua)?;
+        Self::from_function(lua, function.as_function().clone())
+    }
+}
+
+impl<Resume, Yield, Return> TypedThread<Resume, Yield, Return>
+where
+    Resume: IntoLuaMulti,
+    Yield: TypedMultiValue + FromLuaMulti,
+    Return: TypedMultiValue + FromLuaMulti,
+{
+    /// Same as [Thread::resume] but with the resume, yield, and return types already specified
+    ///
+    /// Returns an error if the values yielded or returned by the coroutine do not match their
+    /// declared types.
+    pub fn resume(&self, args: Resume) -> mlua::Result<Resumed<Yield, Return>> {
+        let values = self.inner.resume::<MultiValue>(args)?;
+        let lua = self
+            .lua
+            .try_upgrade()
+            .ok_or_else(|| mlua::Error::runtime("lua instance was destroyed"))?;
+
+        if self.inner.status() == ThreadStatus::Resumable {
+            Yield::from_lua_multi(values, &lua)
+                .map(Resumed::Yield)
+                .map_err(|err| Self::mismatch("yielded", &Yield::get_types(), err))
+        } else {
+            Return::from_lua_multi(values, &lua)
+                .map(Resumed::Return)
+                .map_err(|err| Self::mismatch("returned", &Return::get_types(), err))
+        }
+    }
+
+    fn mismatch(action: &str, types: &[Type], err: mlua::Error) -> mlua::Error {
+        mlua::Error::runtime(format!(
+            "coroutine {action} values that do not match `{}`: {err}",
+            signature(types)
+        ))
+    }
+}
+
+fn signature(types: &[Type]) -> String {
+    types
+        .iter()
+        .filter_map(|ty| DefinitionWriter::type_signature(ty).ok())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl<Resume, Yield, Return> FromLua for TypedThread<Resume, Yield, Return> {
+    fn from_lua(value: Value, lua: &Lua) -> mlua::Result<Self> {
+        Ok(Self::from_thread(lua, FromLua::from_lua(value, lua)?))
+    }
+}
+
+impl<Resume, Yield, Return> IntoLua for TypedThread<Resume, Yield, Return> {
+    fn into_lua(self, _lua: &Lua) -> mlua::Result<Value> {
+        Ok(Value::Thread(self.inner))
+    }
+}
+
+/// thread
+///
+/// As a parameter the resume, yield, and return types are added as the parameter's doc comment
+impl<Resume, Yield, Return> Typed for TypedThread<Resume, Yield, Return>
+where
+    Resume: TypedMultiValue,
+    Yield: TypedMultiValue,
+    Return: TypedMultiValue,
+{
+    fn ty() -> Type {
+        Type::single("thread")
+    }
+
+    fn as_param() -> Param {
+        Param {
+            doc: Some(
+                format!(
+                    "coroutine resumed with `{}`, yields `{}`, and returns `{}`",
+                    signature(&Resume::get_types()),
+                    signature(&Yield::get_types()),
+                    signature(&Return::get_types()),
+                )
+                .into(),
+            ),
+            name: None,
+            ty: Self::ty(),
+        }
+    }
+}
+
+impl<T> TypedFunction<(), Option<T>>
+where
+    T: IntoLua + FromLua + Typed,
+{
+    /// Expose a rust iterator to lua as a generator function.
+    ///
+    /// Each call returns the next item and `nil` once the iterator is exhausted, so it can be used
+    /// directly in a `for ... in` loop.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mlua_extras::{mlua::Lua, typed::TypedFunction};
+    ///
+    /// let lua = Lua::new();
+    /// lua.globals().set("numbers", TypedFunction::generator(&lua, 1..=3)?)?;
+    /// lua.load("for n in numbers do print(n) end").exec()?;
+    /// ```
+    pub fn generator<I>(lua: &Lua, iter: I) -> mlua::Result<Self>
+    where
+        I: IntoIterator<Item = T>,
+        I::IntoIter: MaybeSend + 'static,
+    {
+        let mut iter = iter.into_iter();
+        lua.create_function_mut(move |_, ()| Ok(iter.next()))?
+            .into_typed_function(lua)
+    }
+}
@@ -0,0 +1,382 @@
+use std::fmt::{Display, Formatter, Result, Write};
+
+use super::{function::Return, Param, Type};
+
+/// Writes the type using LuaLS syntax. i.e. `string?`, `table<string, integer>`, `fun(a: number): boolean`
+///
+/// This is also how types are written in generated definition files. Named types such as classes
+/// and modules are written as `table` since they are only known by the name they are registered
+/// with.
+impl Display for Type {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Type::Single(value) | Type::Ref(value) => f.write_str(value),
+            Type::Value(ty) | Type::Alias(ty) => ty.fmt(f),
+            Type::Enum(name, _) => f.write_str(name),
+            Type::Class(_) | Type::Module(_) => f.write_str("table"),
+            Type::Tuple(types) => {
+                f.write_str("{ ")?;
+                for (i, ty) in types.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "[{}]: {ty}", i + 1)?;
+                }
+                f.write_str(" }")
+            }
+            Type::Struct(fields) => {
+                f.write_str("{ ")?;
+                for (i, (name, ty)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    // Optional fields are written as `name?: type`
+                    if ty.is_optional() {
+                        write!(f, "{name}?: {}", ty.without_nil())?;
+                    } else {
+                        write!(f, "{name}: {ty}")?;
+                    }
+                }
+                f.write_str(" }")
+            }
+            Type::Variadic(ty) => write!(f, "...{}", Grouped(ty)),
+            Type::Array(ty) => write!(f, "{}[]", Grouped(ty)),
+            Type::Map(key, value) => write!(f, "table<{key}, {value}>"),
+            Type::Union(types) => {
+                let nil = Type::single("nil");
+                match types.as_slice() {
+                    // A union without members has no values, which is closest to `nil` in LuaLS
+                    [] => f.write_str("nil"),
+                    [ty, other] | [other, ty] if *other == nil && *ty != nil => {
+                        write!(f, "{}?", Grouped(ty))
+                    }
+                    _ => {
+                        for (i, ty) in types.iter().enumerate() {
+                            if i > 0 {
+                                f.write_str(" | ")?;
+                            }
+                            write!(f, "{}", Grouped(ty))?;
+                        }
+                        Ok(())
+                    }
+                }
+            }
+            Type::Function {
+                params,
+                returns,
+                is_async,
+            } => {
+                if *is_async {
+                    f.write_str("async ")?;
+                }
+                f.write_str("fun(")?;
+                write_params(f, params)?;
+                f.write_char(')')?;
+                write_returns(f, returns)
+            }
+        }
+    }
+}
+
+/// Wraps unions and functions in parentheses when they are part of another type
+struct Grouped<'a>(&'a Type);
+
+impl Display for Grouped<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self.0 {
+            Type::Union(types) if types.len() > 1 => write!(f, "({})", self.0),
+            Type::Function { .. } => write!(f, "({})", self.0),
+            other => other.fmt(f),
+        }
+    }
+}
+
+fn write_params(f: &mut Formatter<'_>, params: &[Param]) -> Result {
+    for (i, param) in params.iter().enumerate() {
+        if i > 0 {
+            f.write_str(", ")?;
+        }
+        match param.name.as_deref() {
+            Some(name) => write!(f, "{name}")?,
+            None => write!(f, "param{i}")?,
+        }
+        // Optional params are written as `name?: type`
+        if param.ty.is_optional() {
+            write!(f, "?: {}", param.ty.without_nil())?;
+        } else {
+            write!(f, ": {}", param.ty)?;
+        }
+    }
+    Ok(())
+}
+
+fn write_returns(f: &mut Formatter<'_>, returns: &[Return]) -> Result {
+    for (i, ret) in returns.iter().enumerate() {
+        f.write_str(if i == 0 { ": " } else { ", " })?;
+        write!(f, "{}", ret.ty)?;
+    }
+    Ok(())
+}
+
+impl Type {
+    /// Get the canonical form of the type
+    ///
+    /// - Nested unions are flattened
+    /// - Duplicate union members are removed
+    /// - Unions containing `any` become `any`
+    /// - Union and enum members are sorted, with `nil` last in unions so `T | nil` is written as `T?`
+    /// - Unions with a single member become that member
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mlua_extras::typed::{Type, Typed};
+    ///
+    /// let ty = Option::<Option<String>>::ty().normalize();
+    /// assert_eq!(ty.to_string(), "string?");
+    /// ```
+    pub fn normalize(self) -> Type {
+        match self {
+            Type::Union(types) => {
+                let mut members = Vec::new();
+                for ty in types {
+                    match ty.normalize() {
+                        Type::Union(nested) => members.extend(nested),
+                        other => members.push(other),
+                    }
+                }
+
+                if members.iter().any(|ty| is_single(ty, "any")) {
+                    return Type::single("any");
+                }
+
+                members.sort_by(|a, b| {
+                    is_single(a, "nil")
+                        .cmp(&is_single(b, "nil"))
+                        .then_with(|| a.cmp(b))
+                });
+                members.dedup();
+
+                match members.len() {
+                    1 => members.remove(0),
+                    _ => Type::Union(members),
+                }
+            }
+            Type::Value(ty) => Type::Value(Box::new(ty.normalize())),
+            Type::Alias(ty) => Type::Alias(Box::new(ty.normalize())),
+            Type::Enum(name, types) => {
+                let mut types = types.into_iter().map(Type::normalize).collect::<Vec<_>>();
+                types.sort();
+                types.dedup();
+                Type::Enum(name, types)
+            }
+            Type::Tuple(types) => Type::Tuple(types.into_iter().map(Type::normalize).collect()),
+            Type::Struct(fields) => Type::Struct(
+                fields
+                    .into_iter()
+                    .map(|(name, ty)| (name, ty.normalize()))
+                    .collect(),
+            ),
+            Type::Variadic(ty) => Type::Variadic(Box::new(ty.normalize())),
+            Type::Array(ty) => Type::Array(Box::new(ty.normalize())),
+            Type::Map(key, value) => {
+                Type::Map(Box::new(key.normalize()), Box::new(value.normalize()))
+            }
+            Type::Function {
+                params,
+                returns,
+                is_async,
+            } => Type::Function {
+                params: params
+                    .into_iter()
+                    .map(|param| Param {
+                        ty: param.ty.normalize(),
+                        ..param
+                    })
+                    .collect(),
+                returns: returns
+                    .into_iter()
+                    .map(|ret| Return {
+                        ty: ret.ty.normalize(),
+                        ..ret
+                    })
+                    .collect(),
+                is_async,
+            },
+            other => other,
+        }
+    }
+
+    /// Check if the type is a union that includes `nil`. i.e. from an `Option<T>`
+    pub fn is_optional(&self) -> bool {
+        match self {
+            Type::Union(types) => types.len() > 1 && types.iter().any(|ty| is_single(ty, "nil")),
+            _ => false,
+        }
+    }
+
+    /// Get the type with `nil` removed from it's union members
+    pub(crate) fn without_nil(&self) -> Type {
+        match self {
+            Type::Union(types) => {
+                let mut types = types
+                    .iter()
+                    .filter(|ty| !is_single(ty, "nil"))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                match types.len() {
+                    1 => types.remove(0),
+                    _ => Type::Union(types),
+                }
+            }
+            other => other.clone(),
+        }
+    }
+
+    /// Check if a value of this type can be used where `target` is expected
+    ///
+    /// - `any` is assignable to and from every type
+    /// - Literals are assignable to their base type. i.e. `"text"` to `string` and `3` to `integer`
+    /// - `integer` is assignable to `number`
+    /// - Each member of a union must be assignable to the target
+    /// - Arrays, maps, tuples, and structs are assignable to `table` and functions to `function`
+    /// - Functions accept params that are at least as wide and return values that are at most as
+    ///   wide as the target
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mlua_extras::typed::Type;
+    ///
+    /// let optional = Type::single("integer") | Type::single("nil");
+    /// assert!(Type::single("integer").is_assignable_to(&optional));
+    /// assert!(!optional.is_assignable_to(&Type::single("number")));
+    /// ```
+    pub fn is_assignable_to(&self, target: &Type) -> bool {
+        assignable(&self.clone().normalize(), &target.clone().normalize())
+    }
+
+    /// Check if a value of `other` can be used where this type is expected
+    ///
+    /// Same as `other.is_assignable_to(self)`
+    pub fn accepts(&self, other: &Type) -> bool {
+        other.is_assignable_to(self)
+    }
+}
+
+fn is_single(ty: &Type, name: &str) -> bool {
+    matches!(ty, Type::Single(value) if value == name)
+}
+
+/// Base type of a literal. i.e. `"text"` is a `string` and `3` is an `integer`
+fn literal_base(value: &str) -> Option<&'static str> {
+    if value.len() >= 2
+        && ((value.starts_with('"') && value.ends_with('"'))
+            || (value.starts_with('\'') && value.ends_with('\'')))
+    {
+        Some("string")
+    } else if value == "true" || value == "false" {
+        Some("boolean")
+    } else if value.parse::<i64>().is_ok() {
+        Some("integer")
+    } else if value.parse::<f64>().is_ok() {
+        Some("number")
+    } else {
+        None
+    }
+}
+
+fn assignable(source: &Type, target: &Type) -> bool {
+    if source == target || is_single(source, "any") || is_single(target, "any") {
+        return true;
+    }
+
+    match (source, target) {
+        (Type::Value(source) | Type::Alias(source), target) => assignable(source, target),
+        (source, Type::Value(target) | Type::Alias(target)) => assignable(source, target),
+
+        (Type::Union(types), target) => types.iter().all(|ty| assignable(ty, target)),
+        (source, Type::Union(types)) => types.iter().any(|ty| assignable(source, ty)),
+
+        (Type::Ref(source), Type::Single(target)) | (Type::Single(source), Type::Ref(target)) => {
+            source == target
+        }
+        (Type::Enum(name, _), Type::Single(target) | Type::Ref(target)) if name == target => true,
+        (Type::Single(source) | Type::Ref(source), Type::Enum(name, _)) if source == name => true,
+        (Type::Enum(_, variants), target) => variants.iter().all(|ty| assignable(ty, target)),
+        (source, Type::Enum(_, variants)) => variants.iter().any(|ty| assignable(source, ty)),
+
+        (Type::Single(source), Type::Single(target)) => {
+            match literal_base(source) {
+                Some(base) if base == target => return true,
+                Some("integer") if target == "number" => return true,
+                _ => {}
+            }
+            source == "integer" && target == "number"
+        }
+
+        (
+            Type::Array(_) | Type::Map(_, _) | Type::Tuple(_) | Type::Struct(_) | Type::Module(_),
+            Type::Single(target),
+        ) => target == "table",
+        (Type::Function { .. }, Type::Single(target)) => target == "function",
+
+        (Type::Array(source), Type::Array(target)) => assignable(source, target),
+        (Type::Array(source), Type::Map(key, value)) => {
+            assignable(&Type::single("integer"), key) && assignable(source, value)
+        }
+        (Type::Tuple(types), Type::Array(target)) => types.iter().all(|ty| assignable(ty, target)),
+        (Type::Tuple(source), Type::Tuple(target)) => {
+            source.len() >= target.len()
+                && target
+                    .iter()
+                    .zip(source.iter())
+                    .all(|(t, s)| assignable(s, t))
+        }
+        (Type::Map(source_key, source_value), Type::Map(target_key, target_value)) => {
+            assignable(source_key, target_key) && assignable(source_value, target_value)
+        }
+        (Type::Struct(source), Type::Struct(target)) => {
+            target.iter().all(|(name, ty)| match source.get(name) {
+                Some(field) => assignable(field, ty),
+                None => assignable(&Type::single("nil"), ty),
+            })
+        }
+        (Type::Variadic(source), Type::Variadic(target)) => assignable(source, target),
+
+        (
+            Type::Function {
+                params: source_params,
+                returns: source_returns,
+                ..
+            },
+            Type::Function {
+                params: target_params,
+                returns: target_returns,
+                ..
+            },
+        ) => {
+            let nil = Type::single("nil");
+            // Every param the target may be called with must be accepted
+            let params =
+                source_params
+                    .iter()
+                    .enumerate()
+                    .all(|(i, param)| match target_params.get(i) {
+                        Some(target) => assignable(&target.ty, &param.ty),
+                        None => assignable(&nil, &param.ty),
+                    });
+            // Every value the target promises to return must be returned
+            let returns =
+                target_returns
+                    .iter()
+                    .enumerate()
+                    .all(|(i, ret)| match source_returns.get(i) {
+                        Some(source) => assignable(&source.ty, &ret.ty),
+                        None => assignable(&nil, &ret.ty),
+                    });
+            params && returns
+        }
+        _ => false,
+    }
+}
@@ -0,0 +1,99 @@
+use mlua_extras::typed::{generator::DefinitionParser, Type};
+
+const SOURCE: &str = r#"
+--- @meta
+
+--- A player in the world
+--- @class Player
+--- @field name string The player's name
+--- @field nickname? string
+--- @field [string] integer
+local _Class_Player = {
+    --- @param amount integer
+    --- @return boolean
+    heal = function(self, amount) end,
+}
+
+--- @param name string
+--- @param team? string # Team to join
+--- @return Player
+function spawn(name, team) end
+
+--- @async
+--- @param ms integer
+function sleep(ms) end
+
+--- @alias Mode
+---| "idle"
+---| "running"
+
+--- @enum Color
+Color = { Red = 1, Green = 2 }
+
+--- @type table<string, Player>
+players = {}
+"#;
+
+#[test]
+fn class() {
+    let definition = DefinitionParser::parse(SOURCE).unwrap();
+    let entry = definition
+        .entries
+        .iter()
+        .find(|entry| entry.name == "Player")
+        .unwrap();
+    let Type::Class(class) = &entry.ty else {
+        panic!("expected a class: {:?}", entry.ty);
+    };
+
+    assert_eq!(class.type_doc.as_deref(), Some("A player in the world"));
+    assert_eq!(class.fields["name"].ty, Type::single("string"));
+    assert_eq!(
+        class.fields["name"].doc.as_deref(),
+        Some("The player's name")
+    );
+    assert!(class.fields["nickname"].is_optional());
+    assert_eq!(
+        class.index_fields[&Type::single("string")].ty,
+        Type::single("integer")
+    );
+
+    let heal = &class.methods["heal"];
+    assert_eq!(heal.params.len(), 1);
+    assert_eq!(heal.params[0].name.as_deref(), Some("amount"));
+    assert_eq!(heal.returns.len(), 1);
+}
+
+#[test]
+fn functions() {
+    let definition = DefinitionParser::parse(SOURCE).unwrap();
+    let ty = |name: &str| {
+        definition
+            .entries
+            .iter()
+            .find(|entry| entry.name == name)
+            .map(|entry| entry.ty.to_string())
+    };
+
+    assert_eq!(
+        ty("spawn").as_deref(),
+        Some("fun(name: string, team?: string): Player")
+    );
+    assert_eq!(ty("sleep").as_deref(), Some("async fun(ms: integer)"));
+    assert_eq!(ty("Mode").as_deref(), Some("\"idle\" | \"running\""));
+    assert_eq!(ty("Color").as_deref(), Some("Color"));
+    assert_eq!(ty("players").as_deref(), Some("table<string, Player>"));
+}
+
+#[test]
+fn errors_name_the_line() {
+    let err =
+        DefinitionParser::parse("--- @class Player\n--- @field name table<string").unwrap_err();
+    assert!(err.to_string().contains("line 2:"), "{err}");
+
+    let err = DefinitionParser::parse("--- @field name string").unwrap_err();
+    assert!(
+        err.to_string().contains("@field must follow a @class"),
+        "{err}"
+    );
+}
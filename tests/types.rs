@@ -0,0 +1,118 @@
+use mlua_extras::typed::Type;
+
+/// Types that are written the same way `Display` writes them
+const CANONICAL: &[&str] = &[
+    "string",
+    "Player",
+    "app.Player",
+    "string?",
+    "string[]",
+    "(string | integer)[]",
+    "string[]?",
+    "\"idle\" | \"running\"",
+    "...string",
+    "table<string, Player>",
+    "table<string, fun(): integer>",
+    "{ [1]: string, [2]: integer }",
+    "{ name: string, tags?: string[] }",
+    "fun(evt: Event, ctx?: Ctx): boolean",
+    "async fun(a: number): string, integer",
+];
+
+#[test]
+fn display_round_trip() {
+    for src in CANONICAL {
+        let ty: Type = src.parse().unwrap();
+        assert_eq!(ty.to_string(), *src);
+        assert_eq!(ty.to_string().parse::<Type>().unwrap(), ty, "{src}");
+    }
+}
+
+#[test]
+fn equivalent_syntax() {
+    for (src, display) in [
+        ("string|nil", "string?"),
+        ("{ [string]: integer }", "table<string, integer>"),
+        (
+            "{ name: string; tags?: string[]; }",
+            "{ name: string, tags?: string[] }",
+        ),
+        ("fun(string)", "fun(param0: string)"),
+        ("'idle'|`running`", "\"idle\" | \"running\""),
+    ] {
+        assert_eq!(src.parse::<Type>().unwrap().to_string(), display, "{src}");
+    }
+}
+
+#[test]
+fn references() {
+    assert_eq!("Player".parse::<Type>().unwrap(), Type::reference("Player"));
+    assert_eq!("integer".parse::<Type>().unwrap(), Type::single("integer"));
+}
+
+#[test]
+fn malformed() {
+    for (src, err) in [
+        ("", "expected a type but reached the end of type ``"),
+        (
+            "string integer",
+            "unexpected `integer` in type `string integer`",
+        ),
+        (
+            "string|",
+            "expected a type but reached the end of type `string|`",
+        ),
+        (
+            "table<string",
+            "expected `>` but reached the end of type `table<string`",
+        ),
+        (
+            "{ name string }",
+            "expected `:` but found `string` in type `{ name string }`",
+        ),
+        (
+            "{ a: string, a: integer }",
+            "duplicate table key `a` in type `{ a: string, a: integer }`",
+        ),
+        ("'idle", "unterminated string literal `'idle`"),
+    ] {
+        let result = src.parse::<Type>();
+        assert!(
+            result.as_ref().is_err_and(|e| e.to_string().contains(err)),
+            "{src}: {result:?}"
+        );
+    }
+}
+
+#[cfg(feature = "derive")]
+#[test]
+fn lua_type_matches_from_str() {
+    use mlua_extras::lua_type;
+
+    macro_rules! check {
+        ($($src:literal),* $(,)?) => {
+            $(assert_eq!(lua_type!($src), $src.parse::<Type>().unwrap(), $src);)*
+        };
+    }
+
+    check!(
+        "string",
+        "Player",
+        "app.Player",
+        "string?",
+        "string[]",
+        "(string | integer)[]",
+        "string[]?",
+        "\"idle\" | \"running\"",
+        "...string",
+        "table<string, Player>",
+        "table<string, fun(): integer>",
+        "{ [1]: string, [2]: integer }",
+        "{ name: string, tags?: string[] }",
+        "fun(evt: Event, ctx?: Ctx): boolean",
+        "async fun(a: number): string, integer",
+        "{ [string]: integer }",
+        "fun(string, ...)",
+        "Array<string>",
+    );
+}